@@ -0,0 +1,11 @@
+// 用任意字节调用 Counter::unpack_from_slice：只允许返回 Ok 或 ProgramError，不能panic
+// 运行: cargo +nightly fuzz run unpack_counter
+#![no_main]
+
+use {libfuzzer_sys::fuzz_target, rust_solana::Counter, solana_program::program_pack::Pack};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = <Counter>::unpack_from_slice(data);
+    let _ = <Counter>::unpack(data);
+    let _ = <Counter>::unpack_lenient(data);
+});
@@ -1,47 +1,699 @@
 use solana_sdk::signature::read_keypair_file;
 use {
-    solana_client::rpc_client::RpcClient,
+    base64::{prelude::BASE64_STANDARD, Engine},
+    solana_client::{
+        client_error::ClientError as RpcClientError,
+        nonce_utils,
+        rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+        rpc_config::RpcTransactionConfig,
+    },
     solana_program::{
-        instruction::Instruction,
+        instruction::{Instruction, InstructionError},
+        message::Message,
         program_pack::Pack,
         pubkey::Pubkey,
-        system_instruction,
     },
     solana_sdk::{
+        account::Account,
         commitment_config::CommitmentConfig,
+        hash::Hash,
         native_token::LAMPORTS_PER_SOL,
         signature::Signature,
         signer::{keypair::Keypair, Signer},
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError, VersionedTransaction},
+    },
+    solana_system_interface::instruction as system_instruction,
+    solana_transaction_status_client_types::{option_serializer::OptionSerializer, UiTransactionEncoding},
+    serde_json::{json, Value},
+    std::{
+        cmp::Ordering,
+        fmt::Display,
+        path::Path,
+        str::FromStr,
+        time::{Duration, Instant},
     },
-    std::{error::Error, str::FromStr},
+    thiserror::Error,
 };
 
-use rust_solana::Counter;
+use rust_solana::{
+    authority_instruction,
+    close_instruction,
+    counter_account_space,
+    counter_client::{
+        fetch_counters, rent_status, wait_for_confirmations, CounterClient, CounterLookup, CounterRpc, FailoverRpc,
+    },
+    decode_account_base64, decode_label, encode_label, initialize_with_seed_instruction, pack_instruction_data, unpack_instruction_data, Count, Counter, CounterError,
+    CounterInstruction, LEGACY_COUNTER_LEN, MAX_ACCOUNT_PADDING,
+};
+
+// 客户端错误类型，调用方可以按失败类型分别处理（例如只对RPC错误重试）
+#[derive(Debug, Error)]
+enum ClientError {
+    // RPC请求或交易发送失败（非计数器程序自身的错误）
+    #[error("RPC请求失败: {0}")]
+    Rpc(Box<RpcClientError>),
+    // 程序ID无法解析
+    #[error("无效的程序ID: {0}")]
+    InvalidProgramId(String),
+    // 计数器程序返回的自定义错误
+    #[error("计数器程序返回错误: {0}")]
+    ProgramError(CounterError),
+    // 命令行参数有误
+    #[error("{0}")]
+    InvalidArgument(String),
+    // 交易无法编码、解码或签名校验失败
+    #[error("无效的交易: {0}")]
+    InvalidTransaction(String),
+    // 账户不存在、不属于计数器程序或无法解析为计数器
+    #[error("无效的计数器账户: {0}")]
+    InvalidAccount(String),
+    // 等待的条件在超时前没有满足
+    #[error("等待超时: {0}")]
+    Timeout(String),
+    // --print-data 时已经输出指令数据，交易没有发送；不算失败
+    #[error("--print-data: 交易未发送")]
+    DryRun,
+}
+
+impl From<RpcClientError> for ClientError {
+    fn from(err: RpcClientError) -> Self {
+        // 交易因计数器程序的自定义错误失败时，还原为 CounterError
+        if let Some(TransactionError::InstructionError(_, InstructionError::Custom(code))) =
+            err.get_transaction_error()
+        {
+            if let Ok(counter_error) = CounterError::try_from(code) {
+                return ClientError::ProgramError(counter_error);
+            }
+        }
+        ClientError::Rpc(Box::new(err))
+    }
+}
+
+impl ClientError {
+    // 使用 blockhash 签名的交易是否因区块哈希不存在或已过期而失败。这类失败与交易内容无关，
+    // 换一个新的区块哈希重新签名即可；程序错误、余额不足等其他失败重发也不会成功，不应重试
+    fn is_blockhash_expired(&self, connection: &dyn CounterRpc, blockhash: &Hash) -> bool {
+        let ClientError::Rpc(err) = self else {
+            return false;
+        };
+        match err.get_transaction_error() {
+            Some(TransactionError::BlockhashNotFound) => true,
+            Some(_) => false,
+            // 发送后在区块哈希过期前仍未确认时，RpcClient 不返回具体的交易错误，而其他失败也可能如此，
+            // 因此只在节点确认原来的区块哈希已经失效时才重试；查询失败时不重试
+            None => connection.is_blockhash_valid(blockhash).is_ok_and(|valid| !valid),
+        }
+    }
+}
+
+impl From<Box<RpcClientError>> for ClientError {
+    fn from(err: Box<RpcClientError>) -> Self {
+        ClientError::from(*err)
+    }
+}
+
+
+// 计数器程序ID
+const PROGRAM_ID: &str = "EnKfzEUyaAxGSmFbhD4yezLZ7tXMoQRPcNYVg2Xxi2Cj";
+
+// 本地测试网络的RPC地址
+const RPC_URL: &str = "http://localhost:8899";
+
+// 钱包密钥对文件
+const WALLET_PATH: &str = "wallet-keypair.json";
+
+// 演示流程创建的计数器使用的标签
+const DEMO_LABEL: &str = "demo counter";
+
+// bootstrap 写入的配置文件，之后的命令优先从这里读取程序ID
+const CONFIG_PATH: &str = "counter-client.json";
+
+// cargo build-sbf 生成的程序密钥对，bootstrap 默认从这里读取程序ID
+const PROGRAM_KEYPAIR_PATH: &str = "target/deploy/rust_solana-keypair.json";
+
+// bootstrap 创建的计数器使用的标签
+const BOOTSTRAP_LABEL: &str = "bootstrap counter";
+
+// wait-for 命令默认的超时时间和轮询间隔
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// --confirmations 时等待交易达到指定确认数的超时时间，轮询间隔与 wait-for 相同
+const CONFIRMATIONS_TIMEOUT: Duration = Duration::from_secs(60);
+
+// 钱包余额低于这个值（SOL）时自动空投或警告，足够支付几笔交易和计数器账户的租金；
+// 可以用环境变量 COUNTER_MIN_BALANCE 覆盖，COUNTER_AUTO_AIRDROP=0 时只警告不空投；
+// 命令行的 --min-balance（lamports）和 --no-airdrop 优先于环境变量
+const DEFAULT_MIN_BALANCE: f64 = 0.05;
+const MIN_BALANCE_ENV: &str = "COUNTER_MIN_BALANCE";
+const AUTO_AIRDROP_ENV: &str = "COUNTER_AUTO_AIRDROP";
+
+// 区块哈希过期时换用新的区块哈希重发的最多次数，可以用环境变量 COUNTER_BLOCKHASH_RETRIES 覆盖，0 表示不重试
+const DEFAULT_BLOCKHASH_RETRIES: u32 = 3;
+const BLOCKHASH_RETRIES_ENV: &str = "COUNTER_BLOCKHASH_RETRIES";
+
+// 余额不足时自动空投的数量（SOL）
+const AUTO_AIRDROP_AMOUNT: f64 = 1.0;
+
+// history 命令默认和最多拉取的交易条数
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+const MAX_HISTORY_LIMIT: usize = 1000;
+
+// 命令输出方式：默认面向人的文本；--json 时stdout只输出一行JSON结果，进度信息改写到stderr；
+// --log-json 时进度信息以JSON行写到stderr，便于日志采集系统解析
+#[derive(Clone, Copy)]
+struct Output {
+    json: bool,
+    log_json: bool,
+}
+
+impl Output {
+    // 进度信息
+    fn log(&self, message: impl Display) {
+        if self.log_json {
+            eprintln!("{}", json!({ "timestamp": unix_timestamp(), "message": message.to_string() }));
+        } else if self.json {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    // 交易已确认：JSON日志带上操作名称和签名，文本模式下等同于 log
+    fn tx_confirmed(&self, op: &str, signature: &Signature, message: impl Display) {
+        if self.log_json {
+            eprintln!(
+                "{}",
+                json!({
+                    "timestamp": unix_timestamp(),
+                    "op": op,
+                    "status": "confirmed",
+                    "signature": signature.to_string(),
+                    "message": message.to_string(),
+                })
+            );
+        } else {
+            self.log(message);
+        }
+    }
+
+    // 交易失败：JSON日志带上操作名称和错误，文本模式下等同于 log
+    fn tx_failed(&self, op: &str, err: &ClientError, message: impl Display) {
+        if self.log_json {
+            eprintln!(
+                "{}",
+                json!({
+                    "timestamp": unix_timestamp(),
+                    "op": op,
+                    "status": "failed",
+                    "error": err.to_string(),
+                    "message": message.to_string(),
+                })
+            );
+        } else {
+            self.log(message);
+        }
+    }
+
+    // 命令成功后的结构化结果，文本模式下进度信息已经包含了这些内容
+    fn result(&self, value: Value) {
+        if self.json {
+            println!("{}", value);
+        }
+    }
+
+    // 命令失败
+    fn error(&self, err: &ClientError) {
+        if self.json {
+            println!("{}", json!({ "error": err.to_string() }));
+        } else {
+            eprintln!("错误: {}", err);
+        }
+    }
+}
+
+// 交易指标的采集接口，客户端在每次发送交易前后调用，运维可以接入自己的指标系统
+trait Metrics {
+    // op 为操作名称，latency 为从发送到确认的耗时，success 表示交易是否成功
+    fn record_tx(&self, op: &str, latency: Duration, success: bool);
+}
+
+// 默认实现：不采集任何指标
+struct NoopMetrics;
+
+impl Metrics for NoopMetrics {
+    fn record_tx(&self, _op: &str, _latency: Duration, _success: bool) {}
+}
+
+// 简单实现：把每笔交易的指标作为进度信息输出（--json 模式下走stderr）
+struct StdoutMetrics {
+    output: Output,
+}
+
+impl Metrics for StdoutMetrics {
+    fn record_tx(&self, op: &str, latency: Duration, success: bool) {
+        self.output.log(format_args!(
+            "[metrics] op={} latency_ms={} success={}",
+            op,
+            latency.as_millis(),
+            success
+        ));
+    }
+}
+
+// 当前unix时间戳（秒），用于JSON日志
+fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+// 从参数列表中取出一个开关参数，返回它是否出现过
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let present = args.iter().any(|arg| arg == flag);
+    args.retain(|arg| arg != flag);
+    present
+}
+
+// 从参数列表中取出一个带值的参数；参数出现但缺少值时返回错误
+fn take_value(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, ClientError> {
+    let Some(index) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        return Err(ClientError::InvalidArgument(format!("{} 需要一个参数值", flag)));
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+// 交易使用的区块哈希来源，由 --blockhash 或 --nonce-account 指定
+#[derive(Clone, Copy)]
+enum BlockhashSource {
+    // 直接使用指定的区块哈希（例如可复现的测试）
+    Fixed(Hash),
+    // 使用持久化nonce账户中保存的哈希，交易不会因区块哈希过期而失效，可以离线签名后再提交
+    Nonce(Pubkey),
+}
+
+// 构造和发送交易的选项，在 run 中由命令行参数确定，作为参数传给每个发送交易的函数
+#[derive(Clone, Copy)]
+struct SendOptions {
+    // 交易需要达到的确认级别
+    commitment: CommitmentConfig,
+    // None 时向节点查询最新的区块哈希
+    blockhash: Option<BlockhashSource>,
+    // --confirmations：交易确认后继续等待到这个确认数才视为成功，None 时确认即返回
+    min_confirmations: Option<usize>,
+    // --print-data：输出将要发送的指令数据后返回 ClientError::DryRun，不创建钱包、不空投、不签名也不发送
+    print_data: bool,
+    // --min-balance：钱包最低余额（lamports），优先于 COUNTER_MIN_BALANCE，None 时使用环境变量或默认值
+    min_balance: Option<u64>,
+    // --no-airdrop：余额不足时只警告不空投，优先于 COUNTER_AUTO_AIRDROP
+    no_airdrop: bool,
+}
+
+// 以十六进制输出每条指令的程序ID和 data，与上链的字节完全一致，可以直接交给其他工具重放
+fn print_instruction_data(output: Output, instructions: &[Instruction]) {
+    let hex = |data: &[u8]| data.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+    for (index, ix) in instructions.iter().enumerate() {
+        output.log(format_args!("指令 {} ({}): {}", index, ix.program_id, hex(&ix.data)));
+    }
+    output.result(json!({
+        "instructions": instructions
+            .iter()
+            .map(|ix| json!({ "program_id": ix.program_id.to_string(), "data": hex(&ix.data) }))
+            .collect::<Vec<_>>(),
+    }));
+}
+
+// 为交易准备指令和区块哈希。所有发送交易的命令都经过这里，
+// --print-data 时在这里输出最终的指令并返回 ClientError::DryRun
+fn prepare_transaction(
+    connection: &dyn CounterRpc,
+    output: Output,
+    options: SendOptions,
+    nonce_authority: &Pubkey,
+    instructions: Vec<Instruction>,
+) -> Result<(Vec<Instruction>, Hash), ClientError> {
+    let (instructions, blockhash) = apply_blockhash_source(connection, options, nonce_authority, instructions)?;
+    if options.print_data {
+        print_instruction_data(output, &instructions);
+        return Err(ClientError::DryRun);
+    }
+    Ok((instructions, blockhash))
+}
+
+// 确定交易使用的区块哈希。默认向节点查询最新的区块哈希；使用nonce账户时在指令最前面插入
+// advance_nonce_account（由 nonce_authority 签名），并使用nonce账户中保存的哈希
+fn apply_blockhash_source(
+    connection: &dyn CounterRpc,
+    options: SendOptions,
+    nonce_authority: &Pubkey,
+    mut instructions: Vec<Instruction>,
+) -> Result<(Vec<Instruction>, Hash), ClientError> {
+    let blockhash = match options.blockhash {
+        None => connection.get_latest_blockhash()?,
+        Some(BlockhashSource::Fixed(blockhash)) => blockhash,
+        Some(BlockhashSource::Nonce(nonce_pubkey)) => {
+            let account = connection
+                .get_account(&nonce_pubkey)?
+                .ok_or_else(|| ClientError::InvalidArgument(format!("nonce账户不存在: {}", nonce_pubkey)))?;
+            let data = nonce_utils::data_from_account(&account)
+                .map_err(|err| ClientError::InvalidArgument(format!("{} 不是有效的nonce账户: {}", nonce_pubkey, err)))?;
+            instructions.insert(0, system_instruction::advance_nonce_account(&nonce_pubkey, nonce_authority));
+            data.blockhash()
+        }
+    };
+    Ok((instructions, blockhash))
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let output = Output {
+        json: take_flag(&mut args, "--json"),
+        log_json: take_flag(&mut args, "--log-json"),
+    };
+
+    match run(args, output) {
+        Ok(()) | Err(ClientError::DryRun) => {}
+        Err(err) => {
+            output.error(&err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run(mut args: Vec<String>, output: Output) -> Result<(), ClientError> {
+    output.log(format_args!("启动Solana计数器客户端..."));
+
+    // 默认使用 DEFAULT_COMMITMENT，--finalized 换取更强的不可回滚保证
+    let finalized = take_flag(&mut args, "--finalized");
+
+    let blockhash = take_value(&mut args, "--blockhash")?;
+    let nonce_account = take_value(&mut args, "--nonce-account")?;
+    let source = match (blockhash, nonce_account) {
+        (Some(_), Some(_)) => {
+            return Err(ClientError::InvalidArgument("--blockhash 和 --nonce-account 不能同时使用".to_string()))
+        }
+        (Some(value), None) => {
+            let blockhash = Hash::from_str(&value)
+                .map_err(|_| ClientError::InvalidArgument(format!("无效的区块哈希: {}", value)))?;
+            output.log(format_args!("使用指定的区块哈希: {}", blockhash));
+            Some(BlockhashSource::Fixed(blockhash))
+        }
+        (None, Some(value)) => {
+            let nonce_pubkey = parse_pubkey(&value, "nonce账户地址")?;
+            output.log(format_args!("使用持久化nonce账户: {}", nonce_pubkey));
+            Some(BlockhashSource::Nonce(nonce_pubkey))
+        }
+        (None, None) => None,
+    };
+
+    // --padding 时 demo 和 bootstrap 创建的计数器账户额外预留空间，供以后的布局扩展使用
+    let padding = match take_value(&mut args, "--padding")? {
+        Some(value) => {
+            let padding = value
+                .parse::<u16>()
+                .ok()
+                .filter(|padding| *padding <= MAX_ACCOUNT_PADDING)
+                .ok_or_else(|| {
+                    ClientError::InvalidArgument(format!("无效的预留字节数: {}，最大为 {}", value, MAX_ACCOUNT_PADDING))
+                })?;
+            Some(padding)
+        }
+        None => None,
+    };
+
+    // --confirmations 时交易确认后继续等待，直到达到指定的确认数才视为成功
+    let min_confirmations = match take_value(&mut args, "--confirmations")? {
+        Some(value) => {
+            let confirmations = value
+                .parse::<usize>()
+                .ok()
+                .filter(|confirmations| *confirmations > 0)
+                .ok_or_else(|| ClientError::InvalidArgument(format!("无效的确认数: {}", value)))?;
+            output.log(format_args!("交易需要达到 {} 个确认", confirmations));
+            Some(confirmations)
+        }
+        None => None,
+    };
+
+    // --print-data 时只输出命令将要发送的全部指令数据，不发送
+    let print_data = take_flag(&mut args, "--print-data");
+
+    // --min-balance 和 --no-airdrop 覆盖发送交易前的余额检查使用的环境变量
+    let min_balance = match take_value(&mut args, "--min-balance")? {
+        Some(value) => {
+            let lamports = value
+                .parse::<u64>()
+                .map_err(|_| ClientError::InvalidArgument(format!("无效的最低余额（lamports）: {}", value)))?;
+            Some(lamports)
+        }
+        None => None,
+    };
+    let no_airdrop = take_flag(&mut args, "--no-airdrop");
+
+    // --rpc-fallback 时主节点无法连接或超时的请求改发到备用节点
+    let rpc_fallback = take_value(&mut args, "--rpc-fallback")?;
 
+    // --metrics 时输出每笔交易的耗时和结果
+    let metrics: Box<dyn Metrics> = if take_flag(&mut args, "--metrics") {
+        Box::new(StdoutMetrics { output })
+    } else {
+        Box::new(NoopMetrics)
+    };
+    let metrics = metrics.as_ref();
+
+    // decode 只解析本地数据，不需要程序ID和RPC连接
+    if args.first().map(String::as_str) == Some("decode") {
+        return run_decode(output, &args[1..]);
+    }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    println!("启动Solana计数器客户端...");
+    let program_id = load_program_id(output)?;
 
     // 连接到本地Solana测试网络
-    let rpc_url = String::from("http://localhost:8899");
-    let connection = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+    let client = if finalized {
+        CounterClient::with_commitment(RPC_URL, program_id, CommitmentConfig::finalized())
+    } else {
+        CounterClient::new(RPC_URL, program_id)
+    };
+    let commitment = client.commitment();
+
+    output.log(format_args!("连接到Solana测试网络: {} (确认级别: {:?})", client.url(), commitment.commitment));
+    let fallback = rpc_fallback.map(|url| RpcClient::new_with_commitment(url, commitment));
+    let failover = fallback.as_ref().map(|fallback| {
+        output.log(format_args!("备用RPC节点: {}", fallback.url()));
+        FailoverRpc::new(client.rpc(), fallback, move |message| output.log(format_args!("{}", message)))
+    });
+    let connection: &dyn CounterRpc = match &failover {
+        Some(failover) => failover,
+        None => client.rpc(),
+    };
+    output.log(format_args!("使用程序ID: {}", program_id));
+
+    // demo 用同一个付款人连续发送内容相同的两笔 Increment，固定的区块哈希会让它们完全相同，
+    // 第二笔被节点当作重复交易拒绝，因此 --blockhash 只能用于只发送一笔交易的命令（--print-data 时不发送）
+    let demo = matches!(args.first().map(String::as_str), None | Some("demo"));
+    if demo && !print_data && matches!(source, Some(BlockhashSource::Fixed(_))) {
+        return Err(ClientError::InvalidArgument("--blockhash 不能用于 demo，它会发送多笔交易".to_string()));
+    }
+    let options =
+        SendOptions { commitment, blockhash: source, min_confirmations, print_data, min_balance, no_airdrop };
+
+    match args.first().map(String::as_str) {
+        None | Some("demo") => run_demo(connection, &program_id, options, metrics, output, padding),
+        // 交易历史使用的接口不在 CounterRpc 中，只查询主节点
+        Some("history") => run_history(client.rpc(), &program_id, commitment, output, &args[1..]),
+        Some("build-tx") => run_build_tx(connection, &program_id, options, output, &args[1..]),
+        Some("submit") => run_submit(connection, &program_id, options, metrics, output, &args[1..]),
+        Some("get") => run_get(connection, &program_id, output, &args[1..]),
+        Some("get-many") => run_get_many(connection, &program_id, output, &args[1..]),
+        Some("diff") => run_diff(connection, &program_id, output, &args[1..]),
+        Some("rent") => run_rent(connection, output, &args[1..]),
+        Some("wait-for") => run_wait_for(connection, &program_id, output, &args[1..]),
+        Some("set-authority") => {
+            run_set_authority(connection, &program_id, options, metrics, output, &args[1..])
+        }
+        Some("close") => run_close(connection, &program_id, options, metrics, output, &args[1..]),
+        Some("create-with-seed") => {
+            run_create_with_seed(connection, &program_id, options, metrics, output, &args[1..])
+        }
+        Some("bootstrap") => run_bootstrap(connection, options, metrics, output, padding, &args[1..]),
+        Some(other) => {
+            output.log(format_args!("未知命令: {}", other));
+            output.log(format_args!("用法: counter-client [--finalized] [--json] [--log-json] [--metrics] <命令>"));
+            output.log(format_args!("      [--blockhash 哈希 | --nonce-account 地址]   指定交易使用的区块哈希或持久化nonce"));
+            output.log(format_args!("                                                 （--blockhash 不能用于发送多笔交易的 demo）"));
+            output.log(format_args!("      [--padding 字节数]                         demo/bootstrap 创建账户时额外预留空间"));
+            output.log(format_args!("      [--rpc-fallback 地址]                     主节点无法连接时改用的备用RPC节点"));
+            output.log(format_args!("      [--confirmations N]                        交易达到N个确认后才视为成功"));
+            output.log(format_args!("      [--print-data]                             只输出将要发送的指令数据（十六进制）"));
+            output.log(format_args!("      [--min-balance lamports]                   钱包最低余额，覆盖 COUNTER_MIN_BALANCE"));
+            output.log(format_args!("      [--no-airdrop]                             余额不足时只警告不空投，覆盖 COUNTER_AUTO_AIRDROP"));
+            output.log(format_args!("  demo                                       演示完整流程"));
+            output.log(format_args!("  history <计数器地址> [--limit N] [--before 签名] [--until 签名]"));
+            output.log(format_args!("  build-tx <increment|decrement> <计数器地址> [--fee-payer 地址]"));
+            output.log(format_args!("  submit <base64交易>"));
+            output.log(format_args!("  set-authority <计数器地址> <新授权地址>"));
+            output.log(format_args!("  get <计数器地址>"));
+            output.log(format_args!("  close <计数器地址>                          关闭计数器，租金退回钱包"));
+            output.log(format_args!("  get-many <计数器地址>...                     一次请求读取多个计数器"));
+            output.log(format_args!("  diff <计数器地址A> <计数器地址B>"));
+            output.log(format_args!("  rent [字节数]                               默认为计数器账户大小"));
+            output.log(format_args!("  decode <base64数据或文件>                   离线解码账户数据"));
+            output.log(format_args!("  wait-for <计数器地址> <目标值> [--timeout 秒]"));
+            output.log(format_args!("  create-with-seed <种子> [标签]               以钱包为base派生地址创建计数器"));
+            output.log(format_args!("  bootstrap [程序密钥对文件]                   初始化计数器并写入配置文件"));
+            Err(ClientError::InvalidArgument(format!("未知命令: {}", other)))
+        }
+    }
+}
+
+// 加载计数器程序ID：存在 bootstrap 写入的配置文件时使用其中的程序ID，否则使用 PROGRAM_ID
+fn load_program_id(output: Output) -> Result<Pubkey, ClientError> {
+    if let Ok(contents) = std::fs::read_to_string(CONFIG_PATH) {
+        let config: Value = serde_json::from_str(&contents)
+            .map_err(|err| ClientError::InvalidArgument(format!("无法解析配置文件 {}: {}", CONFIG_PATH, err)))?;
+        let program_id = config["program_id"]
+            .as_str()
+            .ok_or_else(|| ClientError::InvalidArgument(format!("配置文件 {} 缺少 program_id", CONFIG_PATH)))?;
+        output.log(format_args!("从配置文件 {} 读取程序ID", CONFIG_PATH));
+        return Pubkey::from_str(program_id).map_err(|_| ClientError::InvalidProgramId(program_id.to_string()));
+    }
+
+    match Pubkey::from_str(PROGRAM_ID) {
+        Ok(pubkey) => Ok(pubkey),
+        Err(_) => {
+            output.log(format_args!("无效的程序ID: {}，请替换为有效的程序ID", PROGRAM_ID));
+            Err(ClientError::InvalidProgramId(PROGRAM_ID.to_string()))
+        }
+    }
+}
+
+// 构造demo和bootstrap使用的初始化指令；指定了 --padding 时改用 InitializeWithPadding 预留空间
+fn initialize_instruction(label: &str, padding: Option<u16>) -> Result<CounterInstruction, ClientError> {
+    let label = encode_label(label).map_err(|_| ClientError::InvalidArgument(format!("标签过长: {}", label)))?;
+    Ok(match padding {
+        Some(extra) => CounterInstruction::InitializeWithPadding { extra, label },
+        None => CounterInstruction::Initialize { label },
+    })
+}
+
+// 演示完整流程：创建、初始化、增加两次、减少一次
+fn run_demo(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+    padding: Option<u16>,
+) -> Result<(), ClientError> {
+    let program_id = *program_id;
+    let payer = load_or_create_wallet(connection, options, output)?;
+
+    // 请求空投SOL代币用于支付交易费
+    // request_airdrop(connection, &payer.pubkey(), 2.0)?;
+    // output.log(format_args!("已为测试钱包空投 2 SOL"));
 
-    println!("连接到Solana测试网络: {}", rpc_url);
-    
-    // 从文件加载钱包，如果文件不存在则创建新钱包
-    let payer = match read_keypair_file("wallet-keypair.json") {
+    let initialize = initialize_instruction(DEMO_LABEL, padding)?;
+    if options.print_data {
+        return print_demo_instructions(connection, &payer, &program_id, &initialize, options, output);
+    }
+    let (counter_pubkey, _) =
+        create_and_initialize_counter(connection, &payer, &program_id, &initialize, options, metrics, output)?;
+
+    // 休息一下，确保交易被确认
+    // std::thread::sleep(std::time::Duration::from_secs(2));
+
+    // 增加计数器
+    output.log(format_args!("\n执行增加计数器操作..."));
+    increment_counter(connection, &payer, &program_id, &counter_pubkey, options, metrics, output)?;
+
+    // 休息一下，确保交易被确认
+    // std::thread::sleep(std::time::Duration::from_secs(2));
+
+    // 再次增加计数器
+    output.log(format_args!("\n再次执行增加计数器操作..."));
+    increment_counter(connection, &payer, &program_id, &counter_pubkey, options, metrics, output)?;
+
+    // 休息一下，确保交易被确认
+    // std::thread::sleep(std::time::Duration::from_secs(2));
+
+    // 减少计数器
+    output.log(format_args!("\n执行减少计数器操作..."));
+    let signature = decrement_counter(connection, &payer, &program_id, &counter_pubkey, options, metrics, output)?;
+
+    // 获取并显示当前计数
+    let mut count = None;
+    match fetch_counter(connection, &program_id, &counter_pubkey) {
+        Ok(counter) => {
+            output.log(format_args!("\n当前计数: {}", counter.count));
+            output.log(format_args!("计数器标签: {}", decode_label(&counter.label)));
+            output.log(format_args!("授权账户: {}", counter.authority));
+            count = Some(counter.count);
+        },
+        Err(err) => output.log(format_args!("读取计数器失败: {}", err)),
+    }
+
+    output.log(format_args!("\n计数器演示完成！"));
+    output.result(json!({
+        "signature": signature.to_string(),
+        "count": count,
+        "counter": counter_pubkey.to_string(),
+    }));
+    Ok(())
+}
+
+// --print-data 时 demo 不逐笔发送，而是依次构造初始化、两次增加和一次减少四笔交易的指令，
+// 合在一起输出后返回 ClientError::DryRun
+fn print_demo_instructions(
+    connection: &dyn CounterRpc,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    initialize: &CounterInstruction,
+    options: SendOptions,
+    output: Output,
+) -> Result<(), ClientError> {
+    let counter_pubkey = Keypair::new().pubkey();
+    let transactions = [
+        initialize_counter_instructions(connection, payer, program_id, &counter_pubkey, initialize)?,
+        vec![counter_instruction(program_id, &counter_pubkey, &CounterInstruction::Increment)],
+        vec![counter_instruction(program_id, &counter_pubkey, &CounterInstruction::Increment)],
+        vec![counter_instruction(program_id, &counter_pubkey, &CounterInstruction::Decrement)],
+    ];
+    let mut instructions = vec![];
+    for transaction in transactions {
+        instructions.extend(apply_blockhash_source(connection, options, &payer.pubkey(), transaction)?.0);
+    }
+    print_instruction_data(output, &instructions);
+    Err(ClientError::DryRun)
+}
+
+// 从文件加载钱包，如果文件不存在则创建新钱包并空投，最后显示余额。
+// --print-data 时不发送交易，文件不存在时只用一个临时地址构造指令，不空投也不检查余额
+fn load_or_create_wallet(
+    connection: &dyn CounterRpc,
+    options: SendOptions,
+    output: Output,
+) -> Result<Keypair, ClientError> {
+    let payer = match read_keypair_file(WALLET_PATH) {
         Ok(keypair) => {
-            println!("使用已存在的钱包: {}", keypair.pubkey());
+            output.log(format_args!("使用已存在的钱包: {}", keypair.pubkey()));
             keypair
         },
+        Err(_) if options.print_data => {
+            output.log(format_args!("未找到钱包文件，使用临时地址构造指令"));
+            return Ok(Keypair::new());
+        }
         Err(_) => {
-            println!("未找到钱包文件，创建新钱包");
+            output.log(format_args!("未找到钱包文件，创建新钱包"));
             let new_keypair = Keypair::new();
 
             // 请求空投SOL代币用于支付交易费
-            request_airdrop(&connection, &new_keypair.pubkey(), 2.0)?;
-            println!("已为新钱包空投 2 SOL");
+            request_airdrop(connection, &new_keypair.pubkey(), 2.0, options.commitment)?;
+            output.log(format_args!("已为新钱包空投 2 SOL"));
 
             // 等待空投确认
             std::thread::sleep(std::time::Duration::from_secs(3));
@@ -50,34 +702,109 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     };
 
-    // 检查钱包余额
-    let balance = connection.get_balance(&payer.pubkey())?;
-    println!("当前钱包余额: {} SOL", balance as f64 / LAMPORTS_PER_SOL as f64);
-    
-
-    // 请求空投SOL代币用于支付交易费
-    // request_airdrop(&connection, &payer.pubkey(), 2.0)?;
-    // println!("已为测试钱包空投 2 SOL");
+    ensure_min_balance(connection, &payer.pubkey(), options, output)?;
+    Ok(payer)
+}
 
-    // 加载或创建计数器程序ID
-    let program_id_str = "EnKfzEUyaAxGSmFbhD4yezLZ7tXMoQRPcNYVg2Xxi2Cj";
-    let program_id = match Pubkey::from_str(program_id_str) {
-        Ok(pubkey) => pubkey,
-        Err(_) => {
-            println!("无效的程序ID: {}，请替换为有效的程序ID", program_id_str);
-            return Err("无效的程序ID".into());
-        }
+// 检查钱包余额，低于最低余额时在测试网络上自动空投，在其他网络上只输出警告，
+// 避免执行到一半因为余额不足而失败。--print-data 时不会发送交易，不做检查
+fn ensure_min_balance(
+    connection: &dyn CounterRpc,
+    pubkey: &Pubkey,
+    options: SendOptions,
+    output: Output,
+) -> Result<(), ClientError> {
+    if options.print_data {
+        return Ok(());
+    }
+    // 命令行参数是 lamports，环境变量和默认值是 SOL
+    let min_balance = match (options.min_balance, std::env::var(MIN_BALANCE_ENV)) {
+        (Some(lamports), _) => lamports as f64 / LAMPORTS_PER_SOL as f64,
+        (None, Ok(value)) => value
+            .parse::<f64>()
+            .map_err(|_| ClientError::InvalidArgument(format!("无效的 {}: {}", MIN_BALANCE_ENV, value)))?,
+        (None, Err(_)) => DEFAULT_MIN_BALANCE,
     };
-    println!("使用程序ID: {}", program_id);
+    let auto_airdrop = !options.no_airdrop
+        && std::env::var(AUTO_AIRDROP_ENV).map_or(true, |value| value != "0" && value != "false");
 
+    let balance = connection.get_balance(pubkey)? as f64 / LAMPORTS_PER_SOL as f64;
+    output.log(format_args!("当前钱包余额: {} SOL", balance));
+    if balance >= min_balance {
+        return Ok(());
+    }
+
+    // 只有本地、devnet 和 testnet 支持空投
+    let url = connection.url();
+    let test_cluster = ["localhost", "127.0.0.1", "devnet", "testnet"].iter().any(|host| url.contains(host));
+    if test_cluster && auto_airdrop {
+        output.log(format_args!("余额低于 {} SOL，自动空投 {} SOL", min_balance, AUTO_AIRDROP_AMOUNT));
+        request_airdrop(connection, pubkey, AUTO_AIRDROP_AMOUNT, options.commitment)?;
+    } else {
+        output.log(format_args!("警告: 钱包余额低于 {} SOL，后续交易可能因余额不足而失败", min_balance));
+    }
+    Ok(())
+}
+
+// 用新生成的密钥对创建计数器账户并初始化，授权人为付款钱包；返回计数器地址和交易签名
+fn create_and_initialize_counter(
+    connection: &dyn CounterRpc,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    initialize: &CounterInstruction,
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+) -> Result<(Pubkey, Signature), ClientError> {
     // 为计数器创建一个新的账户密钥对
     let counter_keypair = Keypair::new();
     let counter_pubkey = counter_keypair.pubkey();
 
-    println!("创建计数器账户: {}", counter_pubkey);
+    output.log(format_args!("创建计数器账户: {}", counter_pubkey));
+    let instructions = initialize_counter_instructions(connection, payer, program_id, &counter_pubkey, initialize)?;
 
-    // 计算账户需要的空间
-    let counter_space = Counter::LEN;
+    // 获取最近的区块哈希
+    let (instructions, recent_blockhash) =
+        prepare_transaction(connection, output, options, &payer.pubkey(), instructions)?;
+
+    // 创建交易，包括创建账户和初始化两个指令
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &counter_keypair],
+        recent_blockhash,
+    );
+
+    // 发送并确认交易
+    let signers = [payer, &counter_keypair];
+    match send_and_confirm(connection, &transaction, &signers, options, metrics, output, "initialize") {
+        Ok(signature) => {
+            output.tx_confirmed("initialize", &signature, format_args!("计数器初始化交易成功: {}", signature));
+            Ok((counter_pubkey, signature))
+        }
+        Err(err) => {
+            output.tx_failed("initialize", &err, format_args!("计数器初始化交易失败: {}", err));
+            Err(err)
+        }
+    }
+}
+
+// 创建计数器账户并初始化的两条指令，授权人为付款钱包
+fn initialize_counter_instructions(
+    connection: &dyn CounterRpc,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    counter_pubkey: &Pubkey,
+    initialize: &CounterInstruction,
+) -> Result<Vec<Instruction>, ClientError> {
+    let program_id = *program_id;
+    let counter_pubkey = *counter_pubkey;
+
+    // 计算账户需要的空间，InitializeWithPadding 需要额外预留的字节
+    let counter_space = match initialize {
+        CounterInstruction::InitializeWithPadding { extra, .. } => counter_account_space(*extra),
+        _ => <Counter>::LEN,
+    };
 
     // 计算账户所需的租金
     let rent = connection.get_minimum_balance_for_rent_exemption(counter_space)?;
@@ -95,150 +822,964 @@ fn main() -> Result<(), Box<dyn Error>> {
     let initialize_ix = Instruction {
         program_id,
         accounts: vec![
-            solana_program::instruction::AccountMeta::new(counter_pubkey, false),
+            solana_program::instruction::AccountMeta::new(counter_pubkey, true),
+            solana_program::instruction::AccountMeta::new_readonly(payer.pubkey(), true),
         ],
-        data: vec![0], // CounterInstruction::Initialize
+        data: pack_instruction_data(initialize),
     };
 
-    // 获取最近的区块哈希
-    let recent_blockhash = connection.get_latest_blockhash()?;
-
-    // 创建交易，包括创建账户和初始化两个指令
-    let transaction = Transaction::new_signed_with_payer(
-        &[create_account_ix, initialize_ix],
-        Some(&payer.pubkey()),
-        &[&payer, &counter_keypair],
-        recent_blockhash,
-    );
+    Ok(vec![create_account_ix, initialize_ix])
+}
 
-    // 发送并确认交易
-    match connection.send_and_confirm_transaction(&transaction) {
-        Ok(signature) => println!("计数器初始化交易成功: {}", signature),
-        Err(err) => {
-            println!("计数器初始化交易失败: {}", err);
-            return Err(Box::new(err));
+// 首次使用时的一站式设置：从程序密钥对读取程序ID，确认程序已部署，创建并初始化一个计数器，
+// 再把程序ID和计数器地址写入配置文件，之后的命令会自动使用配置文件中的程序ID
+fn run_bootstrap(
+    connection: &dyn CounterRpc,
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+    padding: Option<u16>,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let keypair_path = match args {
+        [] => PROGRAM_KEYPAIR_PATH,
+        [path] => path.as_str(),
+        _ => {
+            return Err(ClientError::InvalidArgument(
+                "用法: counter-client bootstrap [程序密钥对文件]".to_string(),
+            ))
         }
+    };
+    let program_id = read_keypair_file(keypair_path)
+        .map_err(|_| ClientError::InvalidArgument(format!("未找到程序密钥对文件 {}", keypair_path)))?
+        .pubkey();
+    output.log(format_args!("程序ID: {} (来自 {})", program_id, keypair_path));
+
+    // 部署需要 solana CLI 和BPF加载器，这里只检查程序是否已经部署
+    let deployed = connection.get_account(&program_id)?.is_some_and(|account| account.executable);
+    if !deployed {
+        return Err(ClientError::InvalidArgument(format!(
+            "程序 {} 尚未部署，请先运行: solana program deploy target/deploy/rust_solana.so --program-id {}",
+            program_id, keypair_path
+        )));
     }
 
-    // 休息一下，确保交易被确认
-    // std::thread::sleep(std::time::Duration::from_secs(2));
+    let payer = load_or_create_wallet(connection, options, output)?;
+    let initialize = initialize_instruction(BOOTSTRAP_LABEL, padding)?;
+    let (counter_pubkey, signature) =
+        create_and_initialize_counter(connection, &payer, &program_id, &initialize, options, metrics, output)?;
 
-    // 增加计数器
-    println!("\n执行增加计数器操作...");
-    increment_counter(&connection, &payer, &program_id, &counter_pubkey)?;
+    let config = json!({
+        "program_id": program_id.to_string(),
+        "counter": counter_pubkey.to_string(),
+    });
+    let contents = serde_json::to_string_pretty(&config)
+        .map_err(|err| ClientError::InvalidArgument(format!("无法生成配置文件: {}", err)))?;
+    std::fs::write(CONFIG_PATH, contents)
+        .map_err(|err| ClientError::InvalidArgument(format!("无法写入配置文件 {}: {}", CONFIG_PATH, err)))?;
 
-    // 休息一下，确保交易被确认
-    // std::thread::sleep(std::time::Duration::from_secs(2));
+    output.log(format_args!("\n初始化完成："));
+    output.log(format_args!("  程序ID:     {}", program_id));
+    output.log(format_args!("  钱包:       {}", payer.pubkey()));
+    output.log(format_args!("  计数器:     {}", counter_pubkey));
+    output.log(format_args!("  初始化交易: {}", signature));
+    output.log(format_args!("  配置文件:   {}", CONFIG_PATH));
+    output.result(json!({
+        "program_id": program_id.to_string(),
+        "wallet": payer.pubkey().to_string(),
+        "counter": counter_pubkey.to_string(),
+        "signature": signature.to_string(),
+        "config": CONFIG_PATH,
+    }));
+    Ok(())
+}
 
-    // 再次增加计数器
-    println!("\n再次执行增加计数器操作...");
-    increment_counter(&connection, &payer, &program_id, &counter_pubkey)?;
+// 读取并解析计数器账户，账户必须存在、属于计数器程序并且已初始化
+fn fetch_counter(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    counter_pubkey: &Pubkey,
+) -> Result<Counter, ClientError> {
+    fetch_counter_account(connection, program_id, counter_pubkey).map(|(_, counter)| counter)
+}
 
-    // 休息一下，确保交易被确认
-    // std::thread::sleep(std::time::Duration::from_secs(2));
+// 与 fetch_counter 相同，同时返回账户本身，供需要lamports或数据长度的命令使用
+fn fetch_counter_account(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    counter_pubkey: &Pubkey,
+) -> Result<(Account, Counter), ClientError> {
+    let account = connection
+        .get_account(counter_pubkey)?
+        .ok_or_else(|| ClientError::InvalidAccount(format!("账户 {} 不存在", counter_pubkey)))?;
+    if account.owner != *program_id {
+        return Err(ClientError::InvalidAccount(format!(
+            "账户 {} 属于程序 {}，不是计数器账户",
+            counter_pubkey, account.owner
+        )));
+    }
+    let counter = <Counter>::unpack_lenient(&account.data)
+        .map_err(|err| ClientError::InvalidAccount(format!("无法解析账户 {}: {}", counter_pubkey, err)))?;
+    Ok((account, counter))
+}
 
-    // 减少计数器
-    println!("\n执行减少计数器操作...");
-    decrement_counter(&connection, &payer, &program_id, &counter_pubkey)?;
+// 显示单个计数器的状态
+fn run_get(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let counter_arg = match args {
+        [counter] => counter,
+        _ => return Err(ClientError::InvalidArgument("用法: counter-client get <计数器地址>".to_string())),
+    };
+    let counter_pubkey = parse_pubkey(counter_arg, "计数器地址")?;
+    let (account, counter) = fetch_counter_account(connection, program_id, &counter_pubkey)?;
+    let rent = rent_status(connection, &account)?;
 
-    // 获取并显示当前计数
-    match connection.get_account_data(&counter_pubkey) {
-        Ok(data) => {
-            match Counter::unpack(&data) {
-                Ok(counter) => println!("\n当前计数: {}", counter.count),
-                Err(err) => println!("解析计数器数据失败: {}", err),
+    output.log(format_args!("当前计数: {}", counter.count));
+    output.log(format_args!("计数器标签: {}", decode_label(&counter.label)));
+    output.log(format_args!("授权账户: {}", counter.authority));
+    output.log(format_args!("账户余额: {} lamports（{} 字节免租最低 {}）", rent.lamports, account.data.len(),
+        rent.minimum_balance));
+    if !rent.is_exempt() {
+        output.log(format_args!("警告: 账户余额低于免租最低余额，还差 {} lamports，账户可能被回收", rent.shortfall()));
+    }
+    output.result(json!({
+        "counter": counter_pubkey.to_string(),
+        "count": counter.count,
+        "label": decode_label(&counter.label),
+        "authority": counter.authority.to_string(),
+        "lamports": rent.lamports,
+        "rent_exempt_minimum": rent.minimum_balance,
+        "rent_exempt": rent.is_exempt(),
+    }));
+    Ok(())
+}
+
+// 用一次 getMultipleAccounts 请求读取多个计数器并显示各自的计数；
+// 不存在、不属于计数器程序或无法解析的账户单独报告，不影响其他账户
+fn run_get_many(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    if args.is_empty() {
+        return Err(ClientError::InvalidArgument("用法: counter-client get-many <计数器地址>...".to_string()));
+    }
+    let pubkeys = args
+        .iter()
+        .map(|arg| parse_pubkey(arg, "计数器地址"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let lookups = fetch_counters(connection, program_id, &pubkeys)?;
+    let mut results = Vec::with_capacity(lookups.len());
+    for (pubkey, lookup) in pubkeys.iter().zip(lookups) {
+        let result = match lookup {
+            CounterLookup::Counter(counter) => {
+                output.log(format_args!("{}: {}", pubkey, counter.count));
+                json!({ "counter": pubkey.to_string(), "count": counter.count })
             }
-        },
-        Err(err) => println!("获取计数器账户数据失败: {}", err),
+            CounterLookup::Missing => {
+                output.log(format_args!("{}: 账户不存在", pubkey));
+                json!({ "counter": pubkey.to_string(), "error": "账户不存在" })
+            }
+            CounterLookup::WrongOwner(owner) => {
+                let error = format!("属于程序 {}，不是计数器账户", owner);
+                output.log(format_args!("{}: {}", pubkey, error));
+                json!({ "counter": pubkey.to_string(), "error": error })
+            }
+            CounterLookup::Invalid(err) => {
+                let error = format!("无法解析: {}", err);
+                output.log(format_args!("{}: {}", pubkey, error));
+                json!({ "counter": pubkey.to_string(), "error": error })
+            }
+        };
+        results.push(result);
     }
+    output.result(json!({ "counters": results }));
+    Ok(())
+}
+
+// 比较两个计数器的计数，便于对账
+fn run_diff(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let (a_arg, b_arg) = match args {
+        [a, b] => (a, b),
+        _ => {
+            return Err(ClientError::InvalidArgument(
+                "用法: counter-client diff <计数器地址A> <计数器地址B>".to_string(),
+            ))
+        }
+    };
+    let a_pubkey = parse_pubkey(a_arg, "计数器地址A")?;
+    let b_pubkey = parse_pubkey(b_arg, "计数器地址B")?;
+    let a = fetch_counter(connection, program_id, &a_pubkey)?;
+    let b = fetch_counter(connection, program_id, &b_pubkey)?;
 
-    println!("\n计数器演示完成！");
+    output.log(format_args!("A {}: {}", a_pubkey, a.count));
+    output.log(format_args!("B {}: {}", b_pubkey, b.count));
+    let (higher, difference) = match a.count.cmp(&b.count) {
+        Ordering::Greater => (Some("A"), a.count - b.count),
+        Ordering::Less => (Some("B"), b.count - a.count),
+        Ordering::Equal => (None, 0),
+    };
+    match higher {
+        Some(higher) => output.log(format_args!("{} 比另一个多 {}", higher, difference)),
+        None => output.log(format_args!("两个计数器的计数相同")),
+    }
+    output.result(json!({
+        "a": { "counter": a_pubkey.to_string(), "count": a.count },
+        "b": { "counter": b_pubkey.to_string(), "count": b.count },
+        "difference": difference,
+        "higher": higher,
+    }));
     Ok(())
 }
 
-fn increment_counter(
+// 轮询计数器直到计数达到（或超过）目标值，超时后返回错误
+fn run_wait_for(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let usage = "用法: counter-client wait-for <计数器地址> <目标值> [--timeout 秒]";
+    let (counter_arg, target_arg) = match args {
+        [counter, target, ..] => (counter, target),
+        _ => return Err(ClientError::InvalidArgument(usage.to_string())),
+    };
+    let counter_pubkey = parse_pubkey(counter_arg, "计数器地址")?;
+    let target: Count = target_arg
+        .parse()
+        .map_err(|_| ClientError::InvalidArgument(format!("无效的目标值: {}", target_arg)))?;
+    let timeout = match args[2..] {
+        [] => DEFAULT_WAIT_TIMEOUT,
+        [ref flag, ref value] if flag == "--timeout" => Duration::from_secs(
+            value
+                .parse()
+                .map_err(|_| ClientError::InvalidArgument(format!("无效的超时时间: {}", value)))?,
+        ),
+        _ => return Err(ClientError::InvalidArgument(usage.to_string())),
+    };
+
+    let start = Instant::now();
+    loop {
+        match fetch_counter(connection, program_id, &counter_pubkey) {
+            Ok(counter) if counter.count >= target => {
+                output.log(format_args!("计数已达到 {}（目标 {}）", counter.count, target));
+                output.result(json!({ "counter": counter_pubkey.to_string(), "count": counter.count }));
+                return Ok(());
+            }
+            Ok(counter) => output.log(format_args!("当前计数 {}，等待达到 {}...", counter.count, target)),
+            // RPC错误可能是暂时的，继续重试直到超时
+            Err(ClientError::Rpc(err)) => output.log(format_args!("读取计数器失败，稍后重试: {}", err)),
+            Err(err) => return Err(err),
+        }
+
+        if start.elapsed() >= timeout {
+            return Err(ClientError::Timeout(format!(
+                "{} 秒内计数器 {} 未达到 {}",
+                timeout.as_secs(),
+                counter_pubkey,
+                target
+            )));
+        }
+        std::thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+// 查询指定大小的账户免租所需的lamports，默认使用计数器账户的大小
+fn run_rent(connection: &dyn CounterRpc, output: Output, args: &[String]) -> Result<(), ClientError> {
+    let len = match args {
+        [] => <Counter>::LEN,
+        [len] => len
+            .parse()
+            .map_err(|_| ClientError::InvalidArgument(format!("无效的字节数: {}", len)))?,
+        _ => return Err(ClientError::InvalidArgument("用法: counter-client rent [字节数]".to_string())),
+    };
+
+    let lamports = connection.get_minimum_balance_for_rent_exemption(len)?;
+    output.log(format_args!(
+        "{} 字节的账户免租需要 {} lamports（{} SOL）",
+        len,
+        lamports,
+        lamports as f64 / LAMPORTS_PER_SOL as f64
+    ));
+    output.result(json!({ "len": len, "lamports": lamports }));
+    Ok(())
+}
+
+// 离线解码账户数据并显示所有字段，不访问网络，用于检查快照或导出的账户。
+// 参数是存在的文件时读取其中的原始字节，否则按base64解码。
+// 数据比计数器布局长时（例如预留了空间）只解析前 Counter::LEN 字节；比布局短时报告截断
+fn run_decode(output: Output, args: &[String]) -> Result<(), ClientError> {
+    let input = match args {
+        [input] => input,
+        _ => return Err(ClientError::InvalidArgument("用法: counter-client decode <base64数据或文件>".to_string())),
+    };
+    let data = if Path::new(input).is_file() {
+        output.log(format_args!("读取文件: {}", input));
+        std::fs::read(input).map_err(|err| ClientError::InvalidArgument(format!("无法读取文件 {}: {}", input, err)))?
+    } else {
+        decode_account_base64(input.trim())
+            .map_err(|_| ClientError::InvalidArgument(format!("{} 既不是文件也不是有效的base64数据", input)))?
+    };
+
+    output.log(format_args!("数据长度: {} 字节（当前布局需要 {} 字节）", data.len(), <Counter>::LEN));
+    if data.len() == LEGACY_COUNTER_LEN {
+        output.log(format_args!("按最初的 {} 字节布局解析", LEGACY_COUNTER_LEN));
+    } else if data.len() < <Counter>::LEN {
+        return Err(ClientError::InvalidAccount(format!(
+            "数据只有 {} 字节，不足计数器布局的 {} 字节，可能被截断",
+            data.len(),
+            <Counter>::LEN
+        )));
+    } else if data.len() > <Counter>::LEN {
+        output.log(format_args!("忽略末尾多出的 {} 字节", data.len() - <Counter>::LEN));
+    }
+    let counter = <Counter>::unpack_lenient_unchecked(&data)
+        .map_err(|err| ClientError::InvalidAccount(format!("无法解析账户数据: {}", err)))?;
+
+    output.log(format_args!("已初始化: {}", counter.is_initialized));
+    output.log(format_args!("当前计数: {}", counter.count));
+    output.log(format_args!("计数器标签: {}", decode_label(&counter.label)));
+    output.log(format_args!("授权账户: {}", counter.authority));
+    output.log(format_args!("过期时间: {}", counter.expires_at));
+    output.log(format_args!("累计操作次数: {}", counter.total_ops));
+    output.log(format_args!("关联mint: {}", counter.mint));
+    output.log(format_args!("每slot衰减: {}（上次结算slot: {}）", counter.decay_per_slot, counter.last_slot));
+    output.log(format_args!("终身操作上限: {}", counter.lifetime_limit));
+    output.log(format_args!("最短自增间隔: {} 秒（上次自增时间: {}）", counter.min_seconds_between, counter.last_ts));
+    output.log(format_args!("修改中标志: {}", counter.in_progress));
+    output.result(json!({
+        "len": data.len(),
+        "is_initialized": counter.is_initialized,
+        "count": counter.count,
+        "label": decode_label(&counter.label),
+        "authority": counter.authority.to_string(),
+        "expires_at": counter.expires_at,
+        "total_ops": counter.total_ops,
+        "mint": counter.mint.to_string(),
+        "decay_per_slot": counter.decay_per_slot,
+        "last_slot": counter.last_slot,
+        "lifetime_limit": counter.lifetime_limit,
+        "min_seconds_between": counter.min_seconds_between,
+        "last_ts": counter.last_ts,
+        "in_progress": counter.in_progress,
+    }));
+    Ok(())
+}
+
+// 列出计数器账户最近的变更交易，包括指令名称和执行后的计数
+fn run_history(
     connection: &RpcClient,
-    payer: &Keypair,
+    program_id: &Pubkey,
+    commitment: CommitmentConfig,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let counter_pubkey = match args.first() {
+        Some(arg) => parse_pubkey(arg, "计数器地址")?,
+        None => return Err(ClientError::InvalidArgument(
+            "用法: counter-client history <计数器地址> [--limit N] [--before 签名] [--until 签名]".to_string(),
+        )),
+    };
+
+    let mut limit = DEFAULT_HISTORY_LIMIT;
+    let mut before = None;
+    let mut until = None;
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .ok_or_else(|| ClientError::InvalidArgument(format!("参数 {} 缺少取值", flag)))?;
+        match flag.as_str() {
+            "--limit" => {
+                limit = value
+                    .parse()
+                    .map_err(|_| ClientError::InvalidArgument(format!("无效的条数: {}", value)))?
+            }
+            "--before" => before = Some(parse_signature(value)?),
+            "--until" => until = Some(parse_signature(value)?),
+            _ => return Err(ClientError::InvalidArgument(format!("未知参数: {}", flag))),
+        }
+    }
+    let limit = limit.min(MAX_HISTORY_LIMIT);
+
+    output.log(format_args!("查询计数器 {} 最近 {} 条交易记录...", counter_pubkey, limit));
+
+    // 按页拉取签名，每页最多 MAX_HISTORY_LIMIT 条，直到达到上限或没有更多记录
+    let mut signatures = Vec::new();
+    while signatures.len() < limit {
+        let page_limit = limit - signatures.len();
+        let page = connection.get_signatures_for_address_with_config(
+            &counter_pubkey,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: Some(page_limit),
+                commitment: Some(commitment),
+            },
+        )?;
+        let page_len = page.len();
+        if let Some(last) = page.last() {
+            before = Some(parse_signature(&last.signature)?);
+        }
+        signatures.extend(page);
+        if page_len < page_limit {
+            break;
+        }
+    }
+
+    if signatures.is_empty() {
+        output.log(format_args!("没有找到交易记录"));
+    }
+
+    let mut entries = Vec::new();
+    for entry in &signatures {
+        // 失败的交易没有改变计数器状态，跳过
+        if entry.err.is_some() {
+            continue;
+        }
+
+        let signature = parse_signature(&entry.signature)?;
+        let transaction = connection.get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(commitment),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+
+        let names = transaction
+            .transaction
+            .transaction
+            .decode()
+            .map(|tx| counter_instruction_names(&tx, program_id))
+            .unwrap_or_default();
+        if names.is_empty() {
+            continue;
+        }
+
+        let count = transaction
+            .transaction
+            .meta
+            .and_then(|meta| match meta.log_messages {
+                OptionSerializer::Some(logs) => parse_count_from_logs(&logs),
+                _ => None,
+            });
+
+        match count {
+            Some(count) => output.log(format_args!("[slot {}] {} {} -> 计数: {}", entry.slot, entry.signature, names.join(", "), count)),
+            None => output.log(format_args!("[slot {}] {} {}", entry.slot, entry.signature, names.join(", "))),
+        }
+        entries.push(json!({
+            "slot": entry.slot,
+            "signature": entry.signature,
+            "instructions": names,
+            "count": count,
+        }));
+    }
+
+    output.result(json!({ "history": entries }));
+    Ok(())
+}
+
+// 解析命令行中的公钥参数
+fn parse_pubkey(value: &str, what: &str) -> Result<Pubkey, ClientError> {
+    Pubkey::from_str(value).map_err(|_| ClientError::InvalidArgument(format!("无效的{}: {}", what, value)))
+}
+
+// 解析交易签名
+fn parse_signature(value: &str) -> Result<Signature, ClientError> {
+    Signature::from_str(value).map_err(|_| ClientError::InvalidArgument(format!("无效的签名: {}", value)))
+}
+
+// 解析交易中发往计数器程序的变更指令名称
+fn counter_instruction_names(transaction: &VersionedTransaction, program_id: &Pubkey) -> Vec<String> {
+    let account_keys = transaction.message.static_account_keys();
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .filter(|ix| account_keys.get(ix.program_id_index as usize) == Some(program_id))
+        .filter_map(|ix| match unpack_instruction_data(&ix.data) {
+            // 只读指令不改变计数，不计入变更历史
+            Ok(instruction) if !instruction.is_mutating() => None,
+            Ok(instruction) => Some(String::from(instruction.name())),
+            Err(_) => Some(String::from("Unknown")),
+        })
+        .collect()
+}
+
+// 从程序日志中取出最后一次记录的计数，例如 "Program log: Counter incremented to: 2"
+fn parse_count_from_logs(logs: &[String]) -> Option<Count> {
+    logs.iter()
+        .rev()
+        .filter(|line| line.starts_with("Program log: Counter "))
+        .find_map(|line| line.rsplit(": ").next()?.trim().parse().ok())
+}
+
+// 构造只操作单个计数器账户的指令（Increment、Decrement）
+fn counter_instruction(
     program_id: &Pubkey,
     counter_pubkey: &Pubkey,
-) -> Result<(), Box<dyn Error>> {
-    // 创建增加计数的指令
-    let increment_ix = Instruction {
+    instruction: &CounterInstruction,
+) -> Instruction {
+    Instruction {
         program_id: *program_id,
         accounts: vec![
             solana_program::instruction::AccountMeta::new(*counter_pubkey, false),
         ],
-        data: vec![1], // CounterInstruction::Increment
+        data: pack_instruction_data(instruction),
+    }
+}
+
+// 构造未签名的交易并以base64输出，供离线或硬件钱包签名
+fn run_build_tx(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    options: SendOptions,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let usage = "用法: counter-client build-tx <increment|decrement> <计数器地址> [--fee-payer 地址]";
+    let (op, counter_arg) = match args {
+        [op, counter, ..] => (op.as_str(), counter),
+        _ => return Err(ClientError::InvalidArgument(usage.to_string())),
+    };
+
+    let instruction = match op {
+        "increment" => CounterInstruction::Increment,
+        "decrement" => CounterInstruction::Decrement,
+        _ => return Err(ClientError::InvalidArgument(format!("不支持的操作: {}", op))),
     };
+    let counter_pubkey = parse_pubkey(counter_arg, "计数器地址")?;
+
+    // 手续费支付者默认使用本地钱包地址，离线签名时可以通过 --fee-payer 指定
+    let fee_payer = match args[2..] {
+        [ref flag, ref value] if flag == "--fee-payer" => parse_pubkey(value, "手续费支付者地址")?,
+        [] => read_keypair_file(WALLET_PATH)
+            .map_err(|_| {
+                ClientError::InvalidArgument(format!("未找到钱包文件 {}，请使用 --fee-payer 指定地址", WALLET_PATH))
+            })?
+            .pubkey(),
+        _ => return Err(ClientError::InvalidArgument(usage.to_string())),
+    };
+
+    let ix = counter_instruction(program_id, &counter_pubkey, &instruction);
+    let (instructions, recent_blockhash) = prepare_transaction(connection, output, options, &fee_payer, vec![ix])?;
+    let message = Message::new_with_blockhash(&instructions, Some(&fee_payer), &recent_blockhash);
+    let transaction = Transaction::new_unsigned(message);
+
+    output.log(format_args!("未签名交易（最近区块哈希 {}，请在其过期前完成签名并提交）:", recent_blockhash));
+    let serialized = bincode::serialize(&transaction)
+        .map_err(|err| ClientError::InvalidTransaction(err.to_string()))?;
+    let encoded = BASE64_STANDARD.encode(serialized);
+    output.log(format_args!("{}", encoded));
+    output.result(json!({
+        "transaction": encoded,
+        "recent_blockhash": recent_blockhash.to_string(),
+    }));
+    Ok(())
+}
+
+// 反序列化已签名的base64交易并提交
+fn run_submit(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let encoded = match args {
+        [encoded] => encoded,
+        _ => return Err(ClientError::InvalidArgument("用法: counter-client submit <base64交易>".to_string())),
+    };
+
+    let bytes = BASE64_STANDARD
+        .decode(encoded)
+        .map_err(|err| ClientError::InvalidTransaction(format!("无效的base64数据: {}", err)))?;
+    let transaction: Transaction = bincode::deserialize(&bytes)
+        .map_err(|err| ClientError::InvalidTransaction(format!("无法解析交易: {}", err)))?;
+
+    // 提交前先在本地检查签名，避免把未签名或签名不全的交易发出去
+    if !transaction.is_signed() {
+        return Err(ClientError::InvalidTransaction("交易缺少签名，请先完成离线签名".to_string()));
+    }
+    transaction
+        .verify()
+        .map_err(|err| ClientError::InvalidTransaction(format!("交易签名无效: {}", err)))?;
+
+    let signature = match send_and_confirm(connection, &transaction, &[], options, metrics, output, "submit") {
+        Ok(signature) => {
+            output.tx_confirmed("submit", &signature, format_args!("交易提交成功: {}", signature));
+            signature
+        }
+        Err(err) => {
+            output.tx_failed("submit", &err, format_args!("交易提交失败: {}", err));
+            return Err(err);
+        }
+    };
+
+    // 交易中第一条计数器指令的第一个账户就是被操作的计数器，读取提交后的计数
+    let counter_pubkey = transaction
+        .message
+        .instructions
+        .iter()
+        .find(|ix| transaction.message.account_keys.get(ix.program_id_index as usize) == Some(program_id))
+        .and_then(|ix| ix.accounts.first())
+        .and_then(|&index| transaction.message.account_keys.get(index as usize));
+    let count = counter_pubkey
+        .and_then(|pubkey| connection.get_account_data(pubkey).ok())
+        .and_then(|data| <Counter>::unpack_lenient(&data).ok())
+        .map(|counter| counter.count);
+
+    output.result(json!({ "signature": signature.to_string(), "count": count }));
+    Ok(())
+}
+
+// 将计数器的授权转移给新地址，由本地钱包（当前授权账户）签名
+fn run_set_authority(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let (counter_arg, authority_arg) = match args {
+        [counter, authority] => (counter, authority),
+        _ => {
+            return Err(ClientError::InvalidArgument(
+                "用法: counter-client set-authority <计数器地址> <新授权地址>".to_string(),
+            ))
+        }
+    };
+    let counter_pubkey = parse_pubkey(counter_arg, "计数器地址")?;
+    let new_authority = parse_pubkey(authority_arg, "新授权地址")?;
+
+    let authority = read_keypair_file(WALLET_PATH)
+        .map_err(|_| ClientError::InvalidArgument(format!("未找到钱包文件 {}", WALLET_PATH)))?;
+    ensure_min_balance(connection, &authority.pubkey(), options, output)?;
+
+    let set_authority_ix = authority_instruction(
+        program_id,
+        &counter_pubkey,
+        &authority.pubkey(),
+        &CounterInstruction::SetAuthority { new_authority },
+    );
+
+    let (instructions, recent_blockhash) =
+        prepare_transaction(connection, output, options, &authority.pubkey(), vec![set_authority_ix])?;
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&authority.pubkey()),
+        &[&authority],
+        recent_blockhash,
+    );
+
+    let signature =
+        send_and_confirm(connection, &transaction, &[&authority], options, metrics, output, "set-authority")?;
+    output.tx_confirmed(
+        "set-authority",
+        &signature,
+        format_args!("计数器 {} 的授权已转移给 {}: {}", counter_pubkey, new_authority, signature),
+    );
+    output.result(json!({
+        "signature": signature.to_string(),
+        "counter": counter_pubkey.to_string(),
+        "authority": new_authority.to_string(),
+    }));
+    Ok(())
+}
+
+// 关闭计数器并取回租金：由本地钱包（当前授权账户）签名，计数器的全部lamports转回钱包
+fn run_close(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let counter_arg = match args {
+        [counter] => counter,
+        _ => return Err(ClientError::InvalidArgument("用法: counter-client close <计数器地址>".to_string())),
+    };
+    let counter_pubkey = parse_pubkey(counter_arg, "计数器地址")?;
+
+    let authority = read_keypair_file(WALLET_PATH)
+        .map_err(|_| ClientError::InvalidArgument(format!("未找到钱包文件 {}", WALLET_PATH)))?;
+
+    // 发送前确认账户存在、属于计数器程序并且钱包就是授权账户，避免白白支付手续费
+    let (account, counter) = fetch_counter_account(connection, program_id, &counter_pubkey)?;
+    if counter.authority != authority.pubkey() {
+        return Err(ClientError::InvalidArgument(format!(
+            "计数器 {} 的授权账户是 {}，钱包 {} 无法关闭它",
+            counter_pubkey,
+            counter.authority,
+            authority.pubkey()
+        )));
+    }
+    ensure_min_balance(connection, &authority.pubkey(), options, output)?;
+
+    let close_ix = close_instruction(program_id, &counter_pubkey, &authority.pubkey(), &authority.pubkey());
+    let (instructions, recent_blockhash) =
+        prepare_transaction(connection, output, options, &authority.pubkey(), vec![close_ix])?;
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&authority.pubkey()),
+        &[&authority],
+        recent_blockhash,
+    );
+
+    let signature = send_and_confirm(connection, &transaction, &[&authority], options, metrics, output, "close")?;
+    let balance = connection.get_balance(&authority.pubkey())?;
+    output.tx_confirmed("close", &signature, format_args!("计数器 {} 已关闭: {}", counter_pubkey, signature));
+    output.log(format_args!("取回 {} lamports，钱包余额: {} lamports", account.lamports, balance));
+    output.result(json!({
+        "signature": signature.to_string(),
+        "counter": counter_pubkey.to_string(),
+        "reclaimed_lamports": account.lamports,
+        "wallet_balance": balance,
+    }));
+    Ok(())
+}
+
+// 用 create_account_with_seed 创建并初始化计数器，地址由钱包地址、种子和程序ID确定，
+// 同一钱包和种子总是得到同一个地址，不需要保存计数器密钥对
+fn run_create_with_seed(
+    connection: &dyn CounterRpc,
+    program_id: &Pubkey,
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+    args: &[String],
+) -> Result<(), ClientError> {
+    let (seed, label) = match args {
+        [seed] => (seed.as_str(), ""),
+        [seed, label] => (seed.as_str(), label.as_str()),
+        _ => {
+            return Err(ClientError::InvalidArgument(
+                "用法: counter-client create-with-seed <种子> [标签]".to_string(),
+            ))
+        }
+    };
+    let label = encode_label(label).map_err(|_| ClientError::InvalidArgument(format!("标签过长: {}", label)))?;
+
+    let payer = read_keypair_file(WALLET_PATH)
+        .map_err(|_| ClientError::InvalidArgument(format!("未找到钱包文件 {}", WALLET_PATH)))?;
+    ensure_min_balance(connection, &payer.pubkey(), options, output)?;
+
+    // 钱包同时作为base、付款人和授权人
+    let counter_pubkey = Pubkey::create_with_seed(&payer.pubkey(), seed, program_id)
+        .map_err(|err| ClientError::InvalidArgument(format!("无效的种子 {}: {}", seed, err)))?;
+    output.log(format_args!("派生的计数器地址: {}", counter_pubkey));
+
+    let counter_space = <Counter>::LEN;
+    let rent = connection.get_minimum_balance_for_rent_exemption(counter_space)?;
+    let create_account_ix = system_instruction::create_account_with_seed(
+        &payer.pubkey(),
+        &counter_pubkey,
+        &payer.pubkey(),
+        seed,
+        rent,
+        counter_space as u64,
+        program_id,
+    );
+    let initialize_ix = initialize_with_seed_instruction(
+        program_id,
+        &counter_pubkey,
+        &payer.pubkey(),
+        &payer.pubkey(),
+        seed,
+        label,
+    );
+
+    let (instructions, recent_blockhash) =
+        prepare_transaction(connection, output, options, &payer.pubkey(), vec![create_account_ix, initialize_ix])?;
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+
+    let signature =
+        send_and_confirm(connection, &transaction, &[&payer], options, metrics, output, "create-with-seed")?;
+    output.tx_confirmed(
+        "create-with-seed",
+        &signature,
+        format_args!("计数器 {} 创建并初始化成功: {}", counter_pubkey, signature),
+    );
+    output.result(json!({
+        "signature": signature.to_string(),
+        "counter": counter_pubkey.to_string(),
+        "seed": seed,
+    }));
+    Ok(())
+}
+
+fn increment_counter(
+    connection: &dyn CounterRpc,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    counter_pubkey: &Pubkey,
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+) -> Result<Signature, ClientError> {
+    // 创建增加计数的指令
+    let increment_ix = counter_instruction(program_id, counter_pubkey, &CounterInstruction::Increment);
 
     // 获取最近的区块哈希
-    let recent_blockhash = connection.get_latest_blockhash()?;
+    let (instructions, recent_blockhash) =
+        prepare_transaction(connection, output, options, &payer.pubkey(), vec![increment_ix])?;
 
     // 创建交易
     let transaction = Transaction::new_signed_with_payer(
-        &[increment_ix],
+        &instructions,
         Some(&payer.pubkey()),
         &[&payer],
         recent_blockhash,
     );
 
     // 发送并确认交易
-    match connection.send_and_confirm_transaction(&transaction) {
-        Ok(signature) => println!("增加计数器交易成功: {}", signature),
+    match send_and_confirm(connection, &transaction, &[payer], options, metrics, output, "increment") {
+        Ok(signature) => {
+            output.tx_confirmed("increment", &signature, format_args!("增加计数器交易成功: {}", signature));
+            Ok(signature)
+        }
         Err(err) => {
-            println!("增加计数器交易失败: {}", err);
-            return Err(Box::new(err));
+            output.tx_failed("increment", &err, format_args!("增加计数器交易失败: {}", err));
+            Err(err)
         }
     }
-
-    Ok(())
 }
 
 fn decrement_counter(
-    connection: &RpcClient,
+    connection: &dyn CounterRpc,
     payer: &Keypair,
     program_id: &Pubkey,
     counter_pubkey: &Pubkey,
-) -> Result<(), Box<dyn Error>> {
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+) -> Result<Signature, ClientError> {
     // 创建减少计数的指令
-    let decrement_ix = Instruction {
-        program_id: *program_id,
-        accounts: vec![
-            solana_program::instruction::AccountMeta::new(*counter_pubkey, false),
-        ],
-        data: vec![2], // CounterInstruction::Decrement
-    };
+    let decrement_ix = counter_instruction(program_id, counter_pubkey, &CounterInstruction::Decrement);
 
     // 获取最近的区块哈希
-    let recent_blockhash = connection.get_latest_blockhash()?;
+    let (instructions, recent_blockhash) =
+        prepare_transaction(connection, output, options, &payer.pubkey(), vec![decrement_ix])?;
 
     // 创建交易
     let transaction = Transaction::new_signed_with_payer(
-        &[decrement_ix],
+        &instructions,
         Some(&payer.pubkey()),
         &[&payer],
         recent_blockhash,
     );
 
     // 发送并确认交易
-    match connection.send_and_confirm_transaction(&transaction) {
-        Ok(signature) => println!("减少计数器交易成功: {}", signature),
+    match send_and_confirm(connection, &transaction, &[payer], options, metrics, output, "decrement") {
+        Ok(signature) => {
+            output.tx_confirmed("decrement", &signature, format_args!("减少计数器交易成功: {}", signature));
+            Ok(signature)
+        }
         Err(err) => {
-            println!("减少计数器交易失败: {}", err);
-            return Err(Box::new(err));
+            output.tx_failed("decrement", &err, format_args!("减少计数器交易失败: {}", err));
+            Err(err)
         }
     }
+}
+
+// 发送交易并等待其达到指定的确认级别，同时记录每次尝试的耗时和结果。
+// 区块哈希过期时用 signers 以新的区块哈希重新签名并重发，最多 COUNTER_BLOCKHASH_RETRIES 次；
+// signers 为空（例如提交离线签名的交易）或使用 --blockhash/--nonce-account 时不重试，
+// 因为重新获取区块哈希对它们没有意义。使用 --confirmations 时确认后还要等到指定的确认数才返回
+fn send_and_confirm(
+    connection: &dyn CounterRpc,
+    transaction: &Transaction,
+    signers: &[&Keypair],
+    options: SendOptions,
+    metrics: &dyn Metrics,
+    output: Output,
+    op: &str,
+) -> Result<Signature, ClientError> {
+    let max_retries = match std::env::var(BLOCKHASH_RETRIES_ENV) {
+        Ok(value) => value
+            .parse::<u32>()
+            .map_err(|_| ClientError::InvalidArgument(format!("无效的 {}: {}", BLOCKHASH_RETRIES_ENV, value)))?,
+        Err(_) => DEFAULT_BLOCKHASH_RETRIES,
+    };
+    let can_resign = !signers.is_empty() && options.blockhash.is_none();
+
+    let mut transaction = transaction.clone();
+    let mut attempt = 0;
+    loop {
+        let start = Instant::now();
+        let result = connection.send_and_confirm_transaction(&transaction, options.commitment);
+        metrics.record_tx(op, start.elapsed(), result.is_ok());
+        let err = match result {
+            Ok(signature) => {
+                return wait_for_min_confirmations(connection, &signature, options.min_confirmations, output)
+                    .map(|()| signature)
+            }
+            Err(err) => ClientError::from(err),
+        };
+        let blockhash = transaction.message.recent_blockhash;
+        if !can_resign || attempt >= max_retries || !err.is_blockhash_expired(connection, &blockhash) {
+            return Err(err);
+        }
 
+        attempt += 1;
+        output.log(format_args!("区块哈希已过期，使用新的区块哈希重试 ({}/{})", attempt, max_retries));
+        let blockhash = connection.get_latest_blockhash()?;
+        transaction
+            .try_sign(signers, blockhash)
+            .map_err(|err| ClientError::InvalidTransaction(format!("重新签名失败: {}", err)))?;
+    }
+}
+
+// 等待已确认的交易达到要求的确认数，超时返回 ClientError::Timeout；required 为 None 时直接返回
+fn wait_for_min_confirmations(
+    connection: &dyn CounterRpc,
+    signature: &Signature,
+    required: Option<usize>,
+    output: Output,
+) -> Result<(), ClientError> {
+    let Some(required) = required else {
+        return Ok(());
+    };
+    output.log(format_args!("等待交易 {} 达到 {} 个确认...", signature, required));
+    if !wait_for_confirmations(connection, signature, required, CONFIRMATIONS_TIMEOUT, WAIT_POLL_INTERVAL)? {
+        return Err(ClientError::Timeout(format!(
+            "交易 {} 在 {} 秒内没有达到 {} 个确认",
+            signature,
+            CONFIRMATIONS_TIMEOUT.as_secs(),
+            required
+        )));
+    }
     Ok(())
 }
 
-// 请求空投SOL代币
+// 请求空投SOL代币，并等待其达到指定的确认级别
 fn request_airdrop(
-    connection: &RpcClient,
+    connection: &dyn CounterRpc,
     pubkey: &Pubkey,
     amount: f64,
-) -> Result<Signature, Box<dyn Error>> {
-    let sig = connection.request_airdrop(pubkey, (amount * LAMPORTS_PER_SOL as f64) as u64)?;
-    connection.confirm_transaction(&sig)?;
-    Ok(sig)
+    commitment: CommitmentConfig,
+) -> Result<Signature, ClientError> {
+    Ok(connection.request_airdrop(pubkey, (amount * LAMPORTS_PER_SOL as f64) as u64, commitment)?)
 }
\ No newline at end of file
@@ -0,0 +1,611 @@
+// 链下客户端库：封装RPC连接、计数器程序ID和确认级别，命令行客户端基于它构建
+use {
+    crate::{encode_label, pack_instruction_data, Counter, CounterInstruction},
+    solana_client::{
+        client_error::{ClientError as RpcClientError, ClientErrorKind},
+        rpc_client::RpcClient,
+    },
+    solana_program::{
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        rent::Rent,
+    },
+    solana_sdk::{
+        account::Account,
+        commitment_config::CommitmentConfig,
+        signature::{Keypair, Signature, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    solana_system_interface::instruction as system_instruction,
+    std::{
+        cell::RefCell,
+        collections::{HashMap, VecDeque},
+        io,
+        time::{Duration, Instant},
+    },
+};
+
+// 节点在一次 getMultipleAccounts 请求中最多接受的地址数量
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+// 未显式指定时使用的确认级别
+pub const DEFAULT_COMMITMENT: CommitmentConfig = CommitmentConfig::confirmed();
+
+pub struct CounterClient {
+    rpc: RpcClient,
+    program_id: Pubkey,
+}
+
+impl CounterClient {
+    // 使用 DEFAULT_COMMITMENT 连接到url
+    pub fn new(url: impl ToString, program_id: Pubkey) -> Self {
+        Self::with_commitment(url, program_id, DEFAULT_COMMITMENT)
+    }
+
+    // 使用指定的确认级别连接到url，通过 rpc() 发出的请求默认都使用这个级别
+    pub fn with_commitment(url: impl ToString, program_id: Pubkey, commitment: CommitmentConfig) -> Self {
+        CounterClient {
+            rpc: RpcClient::new_with_commitment(url.to_string(), commitment),
+            program_id,
+        }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    pub fn commitment(&self) -> CommitmentConfig {
+        self.rpc.commitment()
+    }
+
+    pub fn url(&self) -> String {
+        self.rpc.url()
+    }
+}
+
+// 命令行客户端用到的RPC子集。RpcClient 是真实实现，MockRpc 在内存中模拟，
+// 使客户端逻辑不依赖运行中的验证节点也能测试。RPC错误较大，统一装箱返回
+pub trait CounterRpc {
+    // 节点地址，用于日志和判断是否为测试网络
+    fn url(&self) -> String;
+
+    fn get_latest_blockhash(&self) -> Result<Hash, Box<RpcClientError>>;
+
+    // 区块哈希是否仍然有效，即使用它签名的交易还能被处理
+    fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool, Box<RpcClientError>>;
+
+    // 发送交易并等待达到commitment
+    fn send_and_confirm_transaction(
+        &self,
+        transaction: &Transaction,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, Box<RpcClientError>>;
+
+    // 按连接的默认确认级别读取账户，账户不存在时返回 None
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>, Box<RpcClientError>>;
+
+    // 一次请求读取多个账户，结果与pubkeys一一对应，不存在的账户为 None。
+    // 节点每次最多接受 MAX_MULTIPLE_ACCOUNTS 个地址
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>, Box<RpcClientError>>;
+
+    // 读取账户数据，账户不存在时返回错误
+    fn get_account_data(&self, pubkey: &Pubkey) -> Result<Vec<u8>, Box<RpcClientError>>;
+
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, Box<RpcClientError>>;
+
+    fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64, Box<RpcClientError>>;
+
+    // 模拟执行交易，不会提交任何修改
+    fn simulate_transaction(&self, transaction: &Transaction) -> Result<Simulation, Box<RpcClientError>>;
+
+    // 查询交易当前的确认数（getSignatureStatuses），节点不知道这笔交易时返回 None
+    fn get_signature_confirmations(&self, signature: &Signature) -> Result<Option<Confirmations>, Box<RpcClientError>>;
+
+    // 请求空投并等待达到commitment，只有测试网络支持
+    fn request_airdrop(
+        &self,
+        pubkey: &Pubkey,
+        lamports: u64,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, Box<RpcClientError>>;
+}
+
+// 模拟交易结果中客户端用到的部分
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Simulation {
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    // 整笔交易消耗的计算单元，包括系统程序等其他指令
+    pub units_consumed: u64,
+}
+
+// 交易状态中的确认数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Confirmations {
+    // 交易所在区块之后已确认的区块数
+    Count(usize),
+    // 交易所在的slot已经成为root（finalized），节点不再统计确认数
+    Rooted,
+}
+
+impl Confirmations {
+    // 是否已经至少有 required 个确认；rooted 的交易满足任何确认数
+    pub fn reached(&self, required: usize) -> bool {
+        match self {
+            Confirmations::Count(count) => *count >= required,
+            Confirmations::Rooted => true,
+        }
+    }
+}
+
+impl CounterRpc for RpcClient {
+    fn url(&self) -> String {
+        RpcClient::url(self)
+    }
+
+    fn get_latest_blockhash(&self) -> Result<Hash, Box<RpcClientError>> {
+        Ok(RpcClient::get_latest_blockhash(self)?)
+    }
+
+    fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool, Box<RpcClientError>> {
+        Ok(RpcClient::is_blockhash_valid(self, blockhash, self.commitment())?)
+    }
+
+    fn send_and_confirm_transaction(
+        &self,
+        transaction: &Transaction,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, Box<RpcClientError>> {
+        Ok(self.send_and_confirm_transaction_with_spinner_and_commitment(transaction, commitment)?)
+    }
+
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>, Box<RpcClientError>> {
+        Ok(self.get_account_with_commitment(pubkey, self.commitment())?.value)
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>, Box<RpcClientError>> {
+        Ok(RpcClient::get_multiple_accounts(self, pubkeys)?)
+    }
+
+    fn get_account_data(&self, pubkey: &Pubkey) -> Result<Vec<u8>, Box<RpcClientError>> {
+        Ok(RpcClient::get_account_data(self, pubkey)?)
+    }
+
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, Box<RpcClientError>> {
+        Ok(RpcClient::get_balance(self, pubkey)?)
+    }
+
+    fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64, Box<RpcClientError>> {
+        Ok(RpcClient::get_minimum_balance_for_rent_exemption(self, data_len)?)
+    }
+
+    fn simulate_transaction(&self, transaction: &Transaction) -> Result<Simulation, Box<RpcClientError>> {
+        let result = RpcClient::simulate_transaction(self, transaction)?.value;
+        Ok(Simulation {
+            err: result.err,
+            logs: result.logs.unwrap_or_default(),
+            units_consumed: result.units_consumed.unwrap_or_default(),
+        })
+    }
+
+    fn get_signature_confirmations(&self, signature: &Signature) -> Result<Option<Confirmations>, Box<RpcClientError>> {
+        let status = RpcClient::get_signature_statuses(self, &[*signature])?.value.into_iter().next().flatten();
+        Ok(status.map(|status| status.confirmations.map_or(Confirmations::Rooted, Confirmations::Count)))
+    }
+
+    fn request_airdrop(
+        &self,
+        pubkey: &Pubkey,
+        lamports: u64,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, Box<RpcClientError>> {
+        let blockhash = RpcClient::get_latest_blockhash(self)?;
+        let signature = self.request_airdrop_with_blockhash(pubkey, lamports, &blockhash)?;
+        self.confirm_transaction_with_spinner(&signature, &blockhash, commitment)?;
+        Ok(signature)
+    }
+}
+
+// 是否为连接层错误（无法连接、超时、HTTP请求失败等），即节点没有正常响应；
+// 节点正常返回的错误（例如交易执行失败、账户不存在）不算
+pub fn is_connection_error(err: &RpcClientError) -> bool {
+    matches!(err.kind(), ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_))
+}
+
+// 带备用节点的RPC：每个请求先发往主节点，出现连接层错误时在备用节点上重试同一请求，
+// 其他错误直接返回。每个请求由哪个节点处理都通过 log 输出。
+// 重试 send_and_confirm_transaction 时发送的是同一笔已签名的交易，即使主节点其实已经转发，交易也只会执行一次
+pub struct FailoverRpc<'a> {
+    primary: &'a dyn CounterRpc,
+    fallback: &'a dyn CounterRpc,
+    log: Box<dyn Fn(&str) + 'a>,
+}
+
+impl<'a> FailoverRpc<'a> {
+    pub fn new(primary: &'a dyn CounterRpc, fallback: &'a dyn CounterRpc, log: impl Fn(&str) + 'a) -> Self {
+        FailoverRpc { primary, fallback, log: Box::new(log) }
+    }
+
+    fn call<T>(
+        &self,
+        method: &str,
+        request: impl Fn(&dyn CounterRpc) -> Result<T, Box<RpcClientError>>,
+    ) -> Result<T, Box<RpcClientError>> {
+        let err = match request(self.primary) {
+            Err(err) if is_connection_error(&err) => err,
+            result => {
+                (self.log)(&format!("{} 由 {} 处理", method, self.primary.url()));
+                return result;
+            }
+        };
+        (self.log)(&format!(
+            "{} 请求主节点 {} 失败: {}，改用备用节点 {}",
+            method,
+            self.primary.url(),
+            err,
+            self.fallback.url()
+        ));
+        let result = request(self.fallback);
+        if !result.as_ref().is_err_and(|err| is_connection_error(err)) {
+            (self.log)(&format!("{} 由 {} 处理", method, self.fallback.url()));
+        }
+        result
+    }
+}
+
+impl CounterRpc for FailoverRpc<'_> {
+    fn url(&self) -> String {
+        self.primary.url()
+    }
+
+    fn get_latest_blockhash(&self) -> Result<Hash, Box<RpcClientError>> {
+        self.call("getLatestBlockhash", |rpc| rpc.get_latest_blockhash())
+    }
+
+    fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool, Box<RpcClientError>> {
+        self.call("isBlockhashValid", |rpc| rpc.is_blockhash_valid(blockhash))
+    }
+
+    fn send_and_confirm_transaction(
+        &self,
+        transaction: &Transaction,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, Box<RpcClientError>> {
+        self.call("sendTransaction", |rpc| rpc.send_and_confirm_transaction(transaction, commitment))
+    }
+
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>, Box<RpcClientError>> {
+        self.call("getAccountInfo", |rpc| rpc.get_account(pubkey))
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>, Box<RpcClientError>> {
+        self.call("getMultipleAccounts", |rpc| rpc.get_multiple_accounts(pubkeys))
+    }
+
+    fn get_account_data(&self, pubkey: &Pubkey) -> Result<Vec<u8>, Box<RpcClientError>> {
+        self.call("getAccountInfo", |rpc| rpc.get_account_data(pubkey))
+    }
+
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, Box<RpcClientError>> {
+        self.call("getBalance", |rpc| rpc.get_balance(pubkey))
+    }
+
+    fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64, Box<RpcClientError>> {
+        self.call("getMinimumBalanceForRentExemption", |rpc| rpc.get_minimum_balance_for_rent_exemption(data_len))
+    }
+
+    fn simulate_transaction(&self, transaction: &Transaction) -> Result<Simulation, Box<RpcClientError>> {
+        self.call("simulateTransaction", |rpc| rpc.simulate_transaction(transaction))
+    }
+
+    fn get_signature_confirmations(&self, signature: &Signature) -> Result<Option<Confirmations>, Box<RpcClientError>> {
+        self.call("getSignatureStatuses", |rpc| rpc.get_signature_confirmations(signature))
+    }
+
+    fn request_airdrop(
+        &self,
+        pubkey: &Pubkey,
+        lamports: u64,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, Box<RpcClientError>> {
+        self.call("requestAirdrop", |rpc| rpc.request_airdrop(pubkey, lamports, commitment))
+    }
+}
+
+// 内存中的模拟RPC：账户由测试预先放入，发送的交易只记录下来、不会执行
+#[derive(Default)]
+pub struct MockRpc {
+    url: String,
+    blockhash: Hash,
+    accounts: RefCell<HashMap<Pubkey, Account>>,
+    sent: RefCell<Vec<Transaction>>,
+    simulation: RefCell<Simulation>,
+    confirmations: RefCell<HashMap<Signature, VecDeque<Confirmations>>>,
+    unreachable: RefCell<bool>,
+}
+
+impl MockRpc {
+    pub fn new(blockhash: Hash) -> Self {
+        MockRpc { blockhash, ..MockRpc::default() }
+    }
+
+    // 指定 url() 返回的地址，默认为空
+    pub fn with_url(mut self, url: impl ToString) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    // 为 true 时之后的所有请求都返回连接被拒绝的错误，模拟节点无法连接
+    pub fn set_unreachable(&self, unreachable: bool) {
+        *self.unreachable.borrow_mut() = unreachable;
+    }
+
+    fn check_reachable(&self) -> Result<(), Box<RpcClientError>> {
+        if *self.unreachable.borrow() {
+            let err = io::Error::new(io::ErrorKind::ConnectionRefused, format!("无法连接到 {}", self.url));
+            return Err(Box::new(ClientErrorKind::Io(err).into()));
+        }
+        Ok(())
+    }
+
+    pub fn set_account(&self, pubkey: Pubkey, account: Account) {
+        self.accounts.borrow_mut().insert(pubkey, account);
+    }
+
+    // 之后每次 simulate_transaction 都返回这个结果
+    pub fn set_simulation(&self, simulation: Simulation) {
+        *self.simulation.borrow_mut() = simulation;
+    }
+
+    // 追加signature之后一次查询返回的确认数。每次查询取出最早的一个，只剩一个时之后一直返回它，
+    // 用来模拟确认数随时间增长
+    pub fn push_confirmations(&self, signature: Signature, confirmations: Confirmations) {
+        self.confirmations.borrow_mut().entry(signature).or_default().push_back(confirmations);
+    }
+
+    // 按发送顺序返回所有交易
+    pub fn sent_transactions(&self) -> Vec<Transaction> {
+        self.sent.borrow().clone()
+    }
+}
+
+impl CounterRpc for MockRpc {
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn get_latest_blockhash(&self) -> Result<Hash, Box<RpcClientError>> {
+        self.check_reachable()?;
+        Ok(self.blockhash)
+    }
+
+    // 只有 get_latest_blockhash 返回的区块哈希有效
+    fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool, Box<RpcClientError>> {
+        self.check_reachable()?;
+        Ok(*blockhash == self.blockhash)
+    }
+
+    // 返回交易的第一个签名，与真实RPC一致
+    fn send_and_confirm_transaction(
+        &self,
+        transaction: &Transaction,
+        _commitment: CommitmentConfig,
+    ) -> Result<Signature, Box<RpcClientError>> {
+        self.check_reachable()?;
+        let signature = transaction.signatures.first().copied().unwrap_or_default();
+        self.sent.borrow_mut().push(transaction.clone());
+        Ok(signature)
+    }
+
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>, Box<RpcClientError>> {
+        self.check_reachable()?;
+        Ok(self.accounts.borrow().get(pubkey).cloned())
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>, Box<RpcClientError>> {
+        self.check_reachable()?;
+        if pubkeys.len() > MAX_MULTIPLE_ACCOUNTS {
+            let message = format!("Too many inputs provided; max {}", MAX_MULTIPLE_ACCOUNTS);
+            return Err(Box::new(ClientErrorKind::Custom(message).into()));
+        }
+        let accounts = self.accounts.borrow();
+        Ok(pubkeys.iter().map(|pubkey| accounts.get(pubkey).cloned()).collect())
+    }
+
+    fn get_account_data(&self, pubkey: &Pubkey) -> Result<Vec<u8>, Box<RpcClientError>> {
+        self.get_account(pubkey)?
+            .map(|account| account.data)
+            .ok_or_else(|| Box::new(ClientErrorKind::Custom(format!("AccountNotFound: pubkey={}", pubkey)).into()))
+    }
+
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, Box<RpcClientError>> {
+        Ok(self.get_account(pubkey)?.map_or(0, |account| account.lamports))
+    }
+
+    fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64, Box<RpcClientError>> {
+        self.check_reachable()?;
+        Ok(Rent::default().minimum_balance(data_len))
+    }
+
+    fn simulate_transaction(&self, _transaction: &Transaction) -> Result<Simulation, Box<RpcClientError>> {
+        self.check_reachable()?;
+        Ok(self.simulation.borrow().clone())
+    }
+
+    fn get_signature_confirmations(&self, signature: &Signature) -> Result<Option<Confirmations>, Box<RpcClientError>> {
+        self.check_reachable()?;
+        let mut confirmations = self.confirmations.borrow_mut();
+        let Some(queue) = confirmations.get_mut(signature) else {
+            return Ok(None);
+        };
+        if queue.len() > 1 {
+            return Ok(queue.pop_front());
+        }
+        Ok(queue.front().copied())
+    }
+
+    // 直接把lamports加到账户上（账户不存在时创建系统账户），立即生效
+    fn request_airdrop(
+        &self,
+        pubkey: &Pubkey,
+        lamports: u64,
+        _commitment: CommitmentConfig,
+    ) -> Result<Signature, Box<RpcClientError>> {
+        self.check_reachable()?;
+        self.accounts.borrow_mut().entry(*pubkey).or_default().lamports += lamports;
+        Ok(Signature::default())
+    }
+}
+
+// fetch_counters 中单个地址的结果
+#[derive(Clone, Debug, PartialEq)]
+pub enum CounterLookup {
+    Counter(Counter),
+    // 账户不存在
+    Missing,
+    // 账户不属于计数器程序，值为实际的所有者
+    WrongOwner(Pubkey),
+    // 账户属于计数器程序，但数据无法解析为已初始化的计数器
+    Invalid(ProgramError),
+}
+
+// 用 getMultipleAccounts 批量读取计数器，结果与pubkeys一一对应。
+// 地址超过 MAX_MULTIPLE_ACCOUNTS 个时分多次请求；单个账户的问题记录在对应的 CounterLookup 中，
+// 只有RPC请求本身失败时才返回错误
+pub fn fetch_counters(
+    rpc: &dyn CounterRpc,
+    program_id: &Pubkey,
+    pubkeys: &[Pubkey],
+) -> Result<Vec<CounterLookup>, Box<RpcClientError>> {
+    let mut lookups = Vec::with_capacity(pubkeys.len());
+    for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = rpc.get_multiple_accounts(chunk)?;
+        lookups.extend(accounts.into_iter().map(|account| match account {
+            None => CounterLookup::Missing,
+            Some(account) if account.owner != *program_id => CounterLookup::WrongOwner(account.owner),
+            Some(account) => match <Counter>::unpack_lenient(&account.data) {
+                Ok(counter) => CounterLookup::Counter(counter),
+                Err(err) => CounterLookup::Invalid(err),
+            },
+        }));
+    }
+    Ok(lookups)
+}
+
+// 每隔 poll_interval 查询一次交易的确认数，直到至少有 required 个确认（或已经 rooted）时返回 true，
+// 超过timeout仍未达到时返回 false。节点最多统计约32个确认，之后交易会变成 rooted，因此较大的 required 同样能结束
+pub fn wait_for_confirmations(
+    rpc: &dyn CounterRpc,
+    signature: &Signature,
+    required: usize,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<bool, Box<RpcClientError>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if rpc.get_signature_confirmations(signature)?.is_some_and(|confirmations| confirmations.reached(required)) {
+            return Ok(true);
+        }
+        if Instant::now() >= deadline {
+            return Ok(false);
+        }
+        std::thread::sleep(poll_interval);
+    }
+}
+
+// 账户余额与免租最低余额的比较结果
+#[derive(Clone, Debug, PartialEq)]
+pub struct RentStatus {
+    pub lamports: u64,
+    // 按账户实际数据长度计算的免租最低余额
+    pub minimum_balance: u64,
+}
+
+impl RentStatus {
+    pub fn is_exempt(&self) -> bool {
+        self.lamports >= self.minimum_balance
+    }
+
+    // 距离免租还差的lamports，已经免租时为0
+    pub fn shortfall(&self) -> u64 {
+        self.minimum_balance.saturating_sub(self.lamports)
+    }
+}
+
+// 查询账户是否免租。最低余额按账户的实际数据长度查询而不是 Counter::LEN，预留了空间的账户需要更多lamports
+pub fn rent_status(rpc: &dyn CounterRpc, account: &Account) -> Result<RentStatus, Box<RpcClientError>> {
+    let minimum_balance = rpc.get_minimum_balance_for_rent_exemption(account.data.len())?;
+    Ok(RentStatus { lamports: account.lamports, minimum_balance })
+}
+
+// estimate_flow_cost 的结果，单位为计算单元
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowCost {
+    pub initialize: u64,
+    pub increment: u64,
+    // 整个流程的总消耗，包括创建账户的系统程序指令
+    pub total: u64,
+}
+
+// 从模拟日志中按顺序取出 program_id 每条指令消耗的计算单元，
+// 对应形如 "Program <id> consumed 1234 of 200000 compute units" 的日志行
+pub fn parse_compute_units(logs: &[String], program_id: &Pubkey) -> Vec<u64> {
+    let prefix = format!("Program {} consumed ", program_id);
+    logs.iter()
+        .filter_map(|line| line.strip_prefix(&prefix))
+        .filter_map(|rest| rest.split_whitespace().next())
+        .filter_map(|units| units.parse().ok())
+        .collect()
+}
+
+// 估算创建、初始化并自增一次计数器的计算单元消耗，供设置计算单元上限和优先费参考。
+// 三条指令放在同一笔交易中模拟，自增才能看到初始化后的账户；只模拟，不会提交任何修改
+pub fn estimate_flow_cost(
+    rpc: &dyn CounterRpc,
+    payer: &Keypair,
+    program_id: &Pubkey,
+) -> Result<FlowCost, Box<RpcClientError>> {
+    let counter = Keypair::new();
+    let rent = rpc.get_minimum_balance_for_rent_exemption(<Counter>::LEN)?;
+    let create_ix = system_instruction::create_account(
+        &payer.pubkey(),
+        &counter.pubkey(),
+        rent,
+        <Counter>::LEN as u64,
+        program_id,
+    );
+    let label = encode_label("estimate").expect("label fits in LABEL_LEN");
+    let initialize_ix = Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), true), AccountMeta::new_readonly(payer.pubkey(), true)],
+        data: pack_instruction_data(&CounterInstruction::Initialize { label }),
+    };
+    let increment_ix = Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), false)],
+        data: pack_instruction_data(&CounterInstruction::Increment),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_ix, initialize_ix, increment_ix],
+        Some(&payer.pubkey()),
+        &[payer, &counter],
+        rpc.get_latest_blockhash()?,
+    );
+    let simulation = rpc.simulate_transaction(&transaction)?;
+    if let Some(err) = simulation.err {
+        return Err(Box::new(ClientErrorKind::Custom(format!("流程模拟失败: {}", err)).into()));
+    }
+
+    match parse_compute_units(&simulation.logs, program_id)[..] {
+        [initialize, increment] => Ok(FlowCost { initialize, increment, total: simulation.units_consumed }),
+        _ => Err(Box::new(ClientErrorKind::Custom("模拟日志中缺少计算单元消耗记录".to_string()).into())),
+    }
+}
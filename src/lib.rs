@@ -1,118 +1,2628 @@
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    entrypoint,
+    clock::Clock,
     entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
     msg,
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
-    pubkey::Pubkey,
+    pubkey::{Pubkey, MAX_SEED_LEN},
     program_pack::{IsInitialized, Pack, Sealed},
-    sysvar::{rent::Rent, Sysvar},
+    rent::Rent,
+    sysvar::Sysvar,
 };
+use thiserror::Error;
+
+#[cfg(feature = "client")]
+pub mod counter_client;
+pub mod math;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// 详细日志：只在启用 verbose 特性时输出。每条 msg! 都要消耗计算单元，生产部署默认只保留
+// 必要的日志（错误原因和 "Counter ... to: N" 结果）。cfg! 在编译期求值，关闭时整条日志连同
+// 参数格式化都会被优化掉，参数仍然参与类型检查
+macro_rules! verbose_msg {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose") {
+            msg!($($arg)*);
+        }
+    };
+}
+
+// 处理步骤跟踪：只在启用 trace 特性时输出，比 verbose_msg! 更细，记录处理函数的每一步
+// （validate 账户校验、unpack 解析、arithmetic 运算、pack 打包）以及涉及的值，
+// 每条日志形如 "trace unpack: count=5 total_ops=2"，便于按步骤过滤。与 verbose_msg! 一样关闭时不消耗计算单元
+macro_rules! trace {
+    ($step:literal, $($arg:tt)*) => {
+        if cfg!(feature = "trace") {
+            msg!(concat!("trace ", $step, ": {}"), format_args!($($arg)*));
+        }
+    };
+}
+
+// 计数器程序的自定义错误，以 ProgramError::Custom(错误码) 的形式返回
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CounterError {
+    // 计数值超出目标整数类型的范围
+    #[error("Count does not fit in the target integer type")]
+    ValueTooLarge,
+    // CompareAndSet 时当前值与期望值不一致
+    #[error("Current count does not match the expected value")]
+    Conflict,
+    // Spend 时计数不足以支付amount
+    #[error("Count is lower than the amount to spend")]
+    InsufficientBalance,
+    // 账户数据的校验和与内容不符，数据被部分写入或被外部修改
+    #[error("Counter account data failed its checksum")]
+    CorruptData,
+    // 计数器已过期，只允许 Close
+    #[error("Counter has expired")]
+    Expired,
+    // 注册表已记录 REGISTRY_CAPACITY 个计数器
+    #[error("Counter registry is full")]
+    RegistryFull,
+    // 传入的账户比指令能使用的多，通常是客户端用错了指令或账户列表
+    #[error("Too many accounts for this instruction")]
+    TooManyAccounts,
+    // 初始化之后的修改次数已达到 lifetime_limit，不再允许自增
+    #[error("Counter has used up its lifetime increment limit")]
+    LifetimeExhausted,
+    // 距上次自增还不到 min_seconds_between 秒
+    #[error("Counter was incremented too recently")]
+    TooSoon,
+    // 计数器账户带有 in_progress 标志，另一个修改它的指令还没有结束
+    #[error("Counter is already being modified")]
+    Reentrancy,
+}
+
+impl From<CounterError> for ProgramError {
+    fn from(e: CounterError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+// 由 ProgramError::Custom 中的错误码还原 CounterError，供客户端解析失败原因
+impl TryFrom<u32> for CounterError {
+    type Error = ProgramError;
+
+    fn try_from(code: u32) -> Result<Self, Self::Error> {
+        match code {
+            0 => Ok(CounterError::ValueTooLarge),
+            1 => Ok(CounterError::Conflict),
+            2 => Ok(CounterError::InsufficientBalance),
+            3 => Ok(CounterError::CorruptData),
+            4 => Ok(CounterError::Expired),
+            5 => Ok(CounterError::RegistryFull),
+            6 => Ok(CounterError::TooManyAccounts),
+            7 => Ok(CounterError::LifetimeExhausted),
+            8 => Ok(CounterError::TooSoon),
+            9 => Ok(CounterError::Reentrancy),
+            _ => Err(ProgramError::Custom(code)),
+        }
+    }
+}
+
+// 计数器标签的固定长度（UTF-8，不足部分补0）
+pub const LABEL_LEN: usize = 32;
+
+// IncrementUntil 单次调用最多执行的自增次数，避免超出计算单元上限
+pub const MAX_INCREMENT_ITERATIONS: u32 = 256;
+
+// 幂等键缓存保存的最近键数量，超出后覆盖最早的键
+pub const IDEMPOTENCY_CACHE_SIZE: usize = 16;
+
+// 已初始化幂等键缓存的第一个字节，与 REGISTRY_DISCRIMINATOR 的作用相同：
+// 缓存与计数器的长度可能相同，discriminator 保证两者不会互相被当作对方读写
+pub const IDEMPOTENCY_CACHE_DISCRIMINATOR: u8 = 0x49;
+
+// 注册表最多记录的计数器数量；ListCounters 通过 return data 返回全部公钥，
+// 32 * 32 字节正好是 return data 的上限 MAX_RETURN_DATA
+pub const REGISTRY_CAPACITY: usize = 32;
+
+// 已初始化注册表的第一个字节。计数器账户的第一个字节是 is_initialized（0或1），
+// 因此长度恰好等于 Registry::LEN 的计数器账户也不会被当作注册表读写
+pub const REGISTRY_DISCRIMINATOR: u8 = 0x52;
+
+// InitializeWithPadding 允许在 Counter::LEN 之后预留的最大字节数
+pub const MAX_ACCOUNT_PADDING: u16 = 1024;
+
+// InitializeBatch 单条指令最多创建的计数器数量。每个计数器需要一次 find_program_address
+// 和一次创建账户的CPI，上限保证整条指令留在默认的20万计算单元以内，并给同一笔交易的其他指令留出余量
+pub const MAX_BATCH_INITIALIZE: u8 = 8;
+
+// InitializeBatch 派生计数器地址使用的种子前缀，完整种子为 [BATCH_COUNTER_SEED, base, 序号]
+pub const BATCH_COUNTER_SEED: &[u8] = b"counter";
 
 // 定义计数器指令类型
 #[derive(Debug, PartialEq)]
 pub enum CounterInstruction {
-    // 初始化计数器账户，从0开始
-    Initialize,
-    // 增加计数器的值
+    // 初始化计数器账户，从0开始，并写入标签
+    // 账户: [可写, 签名] 计数器账户, [签名] 授权账户
+    Initialize { label: [u8; LABEL_LEN] },
+    // 增加计数器的值。附带授权账户时它必须是计数器记录的authority并且签名，否则任何人都可以调用
+    // 账户: [可写] 计数器账户, 可选的 [签名] 授权账户
     Increment,
     // 减少计数器的值
+    // 账户: [可写] 计数器账户
     Decrement,
+    // 修改计数器标签，需要授权账户签名
+    // 账户: [可写] 计数器账户, [签名] 授权账户
+    Relabel { label: [u8; LABEL_LEN] },
+    // 当前值等于expected时写入new，否则返回 CounterError::Conflict，需要授权账户签名
+    // 账户: [可写] 计数器账户, [签名] 授权账户
+    CompareAndSet { expected: u32, new: u32 },
+    // 只读：通过 return data 返回授权账户公钥的32字节
+    // 账户: [] 计数器账户
+    GetAuthority,
+    // 反复自增直到达到target，单次最多 MAX_INCREMENT_ITERATIONS 次，
+    // 通过 return data 返回本次实际执行的次数（小端u32），未达到目标时客户端可以再次调用
+    // 账户: [可写] 计数器账户
+    IncrementUntil { target: u32 },
+    // 将传入的每个计数器减1（到0为止不再减少），用于批量"tick"
+    // skip_invalid 为 true 时跳过不属于本程序、无法解析、已过期或正在修改中的账户，否则遇到即整体失败
+    // 账户: [可写] 计数器账户 ...
+    DecrementAll { skip_invalid: bool },
+    // 带幂等键的自增：键在缓存中出现过则直接成功返回、不修改计数，否则自增并记录该键
+    // 缓存账户首次使用时绑定到该计数器
+    // 账户: [可写] 计数器账户, [可写] 幂等键缓存账户
+    IncrementIdempotent { key: u64 },
+    // 把计数当作余额使用：count >= amount 时减去amount，否则返回 CounterError::InsufficientBalance，
+    // 不会减到0为止
+    // 账户: [可写] 计数器账户
+    Spend { amount: u32 },
+    // 将授权转移给new_authority，需要当前授权账户签名
+    // 账户: [可写] 计数器账户, [签名] 当前授权账户
+    SetAuthority { new_authority: Pubkey },
+    // 只读：通过 return data 一次性返回计数器状态，布局固定为
+    //   [0]                   is_initialized（0或1）
+    //   [1..1 + Count::WIDTH] count（小端，与账户的存储字节序无关）
+    //   [1 + Count::WIDTH..9 + Count::WIDTH]  total_ops（小端u64，共8字节）
+    //   [9 + Count::WIDTH..41 + Count::WIDTH]  mint（32字节，未关联时为全零）
+    //   [41 + Count::WIDTH..] last_slot（小端u64，衰减结算到的slot；不衰减的计数器为0）
+    // 未初始化的账户也会返回（各字段为0），账户没有创建时间字段
+    // 账户: [] 计数器账户
+    GetStats,
+    // 需要授权账户签名的批量减少，减到0以下时失败
+    // 账户: [可写] 计数器账户, [签名] 授权账户
+    AuthDecrementBy { amount: u32 },
+    // 管理员修正：直接把计数设为value，需要授权账户签名
+    // 账户: [可写] 计数器账户, [签名] 授权账户
+    AuthSetValue { value: u32 },
+    // 尝试减去amount；不够减时不做修改但仍然成功，让客户端不必用一笔失败的交易来探测余额。
+    // return data 布局固定为
+    //   [0]                   是否已扣减（0或1）
+    //   [1..1 + Count::WIDTH] 扣减后的计数，未扣减时为当前可用的计数（小端）
+    // 账户: [可写] 计数器账户
+    DecrementByReport { amount: u32 },
+    // 初始化后立即自增到1，省去首次使用时的第二笔交易；已初始化的账户会被拒绝
+    // 账户: 与 Initialize 相同
+    InitializeAndIncrement { label: [u8; LABEL_LEN] },
+    // 按顺序依次加上每个amount，每一步都检查溢出；任何一步溢出时整体失败，日志中记录出错的步骤。
+    // amounts 为空时直接成功返回，不修改计数器
+    // 指令数据: 小端u32的个数，后接对应数量的小端u32
+    // 账户: [可写] 计数器账户
+    IncrementSequence { amounts: Vec<u32> },
+    // 从第一个计数器中减去第二个计数器的当前计数，第二个计数器只读不修改；减到0以下时失败
+    // 账户: [可写] 目标计数器账户, [] 提供数量的计数器账户
+    DecrementByAccount,
+    // 关闭计数器：把全部lamports转给目标账户并清零账户数据，需要授权账户签名
+    // 账户: [可写] 计数器账户, [签名] 授权账户, [可写] 接收lamports的账户
+    Close,
+    // 仅当当前计数严格小于threshold时自增，否则直接成功返回、不修改账户；阈值由每次调用指定
+    // 账户: [可写] 计数器账户
+    IncrementIfBelow { threshold: u32 },
+    // 只读：通过 return data 以ASCII十进制字符串返回计数，不带符号、前导零或结尾符，
+    // 长度不超过 COUNT_STRING_MAX_LEN 字节
+    // 账户: [] 计数器账户
+    GetCountString,
+    // 初始化计数器并把授权设为指定的公钥，创建者不必是授权人（委托场景）；标签为空
+    // 账户: [可写, 签名] 计数器账户
+    InitializeWithAuthority { authority: Pubkey },
+    // 与 Initialize 相同，同时设置过期时间expires_at（unix时间戳，0 表示永不过期）
+    // 指令数据: 小端i64的expires_at，后接标签
+    // 账户: 与 Initialize 相同
+    InitializeWithExpiry { expires_at: i64, label: [u8; LABEL_LEN] },
+    // 初始化由 create_account_with_seed 创建的计数器账户，地址由 base + seed + program_id 派生；
+    // 计数器账户不需要签名，改由base账户签名并校验派生地址
+    // 指令数据: 单字节种子长度（不超过 MAX_SEED_LEN），种子UTF-8字节，后接标签
+    // 账户: [可写] 计数器账户, [签名] 授权账户, [签名] base账户
+    InitializeWithSeed { seed: String, label: [u8; LABEL_LEN] },
+    // 只读：通过 return data 返回注册表中按创建顺序排列的计数器公钥（每个32字节）
+    // 账户: [] 注册表账户
+    ListCounters,
+    // 原子地交换两个计数器的计数，其余字段保持不变；两个账户必须不同且都已初始化
+    // 账户: [可写] 第一个计数器账户, [可写] 第二个计数器账户
+    Swap,
+    // 计数减少amount，同时由付款账户向接收账户转账amount lamports，两者在同一条指令中原子完成；
+    // 计数不足时返回 InvalidArgument，付款账户余额不足时返回 InsufficientFunds
+    // 账户: [可写] 计数器账户, [可写, 签名] 付款账户, [可写] 接收账户, [] 系统程序
+    DecrementAndPay { amount: u32 },
+    // 只读：检查账户是否为当前格式，通过 return data 返回一个 AccountStatus 字节，不会因账户格式问题而失败
+    // 账户: [] 计数器账户
+    Validate,
+    // 把当前纪元号（Clock sysvar 的 epoch）加到计数上，溢出或纪元号超出计数范围时失败
+    // 账户: [可写] 计数器账户
+    IncrementByEpoch,
+    // 读取并清零：通过 return data 返回清零前的小端计数，同时把计数置为0，需要授权账户签名
+    // 账户: [可写] 计数器账户, [签名] 授权账户
+    GetAndReset,
+    // 与 Initialize 相同，同时把计数器关联到一个SPL代币mint，便于工具按代币对计数器分组；
+    // 只要求是格式正确的公钥，不会读取或校验mint账户
+    // 指令数据: 32字节的mint，后接标签
+    // 账户: 与 Initialize 相同
+    InitializeWithMint { mint: Pubkey, label: [u8; LABEL_LEN] },
+    // 只读：通过 return data 返回关联的mint公钥（32字节），未关联时为全零
+    // 账户: [] 计数器账户
+    GetMint,
+    // 仅当标志账户数据中 offset 处的字节非零时自增，否则直接成功返回、不修改账户。
+    // 标志账户可以属于任意程序且只读，offset 超出其数据长度时返回 InvalidArgument
+    // 账户: [可写] 计数器账户, [] 标志账户
+    IncrementIfFlag { offset: u32 },
+    // 与 Initialize 相同，但账户在 Counter::LEN 之后额外预留 extra 字节，供以后的布局扩展使用而不必重新分配；
+    // 账户数据长度不得小于 Counter::LEN + extra，extra 不得超过 MAX_ACCOUNT_PADDING
+    // 指令数据: 小端u16的extra，后接标签
+    // 账户: 与 Initialize 相同
+    InitializeWithPadding { extra: u16, label: [u8; LABEL_LEN] },
+    // 与 Initialize 相同，但计数每经过一个slot自动减少 decay_per_slot（最低为0），不需要定时任务。
+    // 所有读取和修改计数器的指令都会先按当前slot结算衰减，因此会多读取一次 Clock sysvar，
+    // 开销略高于普通计数器；decay_per_slot 为 0 时等同于 Initialize
+    // 指令数据: 小端u32的decay_per_slot，后接标签
+    // 账户: 与 Initialize 相同
+    InitializeWithDecay { decay_per_slot: u32, label: [u8; LABEL_LEN] },
+    // 与 Initialize 相同，但限制计数器终身可以自增的次数，用于配额：初始化之后的成功修改次数
+    // （total_ops 减去初始化那一次）达到 lifetime_limit 后，所有自增指令都返回 CounterError::LifetimeExhausted，
+    // 即使当前计数已经被减少。减少计数同样计入 total_ops，不会恢复额度；lifetime_limit 为 0 时等同于 Initialize
+    // 指令数据: 小端u64的lifetime_limit，后接标签
+    // 账户: 与 Initialize 相同
+    InitializeWithLifetimeLimit { lifetime_limit: u64, label: [u8; LABEL_LEN] },
+    // 与 Initialize 相同，但两次自增之间至少间隔 min_seconds_between 秒（按 Clock sysvar 的 unix_timestamp），
+    // 间隔不足时所有自增指令都返回 CounterError::TooSoon 并在日志中给出剩余的冷却时间；
+    // 减少计数不受限制。min_seconds_between 为 0 时等同于 Initialize
+    // 指令数据: 小端u32的min_seconds_between，后接标签
+    // 账户: 与 Initialize 相同
+    InitializeWithCooldown { min_seconds_between: u32, label: [u8; LABEL_LEN] },
+    // 按模板计数器初始化：授权人、标签、过期时间、mint、衰减、终身额度和自增间隔都从模板复制，
+    // 便于批量创建配置相同的计数器而不必在每条指令里重复配置。计数从0开始，与模板的计数无关；
+    // 模板必须属于当前程序、已初始化且未过期，只读不修改。授权人取自模板，因此不需要授权账户签名
+    // 账户: [可写, 签名] 计数器账户, [] 模板计数器账户
+    InitializeFromTemplate,
+    // 在一条指令中创建并初始化count个PDA计数器，地址为 batch_counter_address(program_id, base, 0..count)，
+    // 由程序通过 invoke_signed 代签创建；授权人为base，所有计数器使用同一个标签。
+    // 账户必须尚未创建，付款账户需要支付全部免租余额；任何一个计数器失败时整条指令失败，不会留下部分计数器。
+    // count 为 1 到 MAX_BATCH_INITIALIZE
+    // 指令数据: 单字节count，后接标签
+    // 账户: [可写, 签名] 付款账户, [签名] base账户, [] 系统程序, [可写] count个计数器账户（按序号排列）
+    InitializeBatch { count: u8, label: [u8; LABEL_LEN] },
+    // 只读：通过 return data 以小端u32返回 count % divisor，供循环显示等场景使用，不必读取完整计数；
+    // divisor 为 0 时返回 InvalidArgument
+    // 指令数据: 小端u32的divisor
+    // 账户: [] 计数器账户
+    GetCountMod { divisor: u32 },
+    // 只读：通过 return data 返回本次编译的程序配置，客户端据此适配已部署的程序，而不必硬编码账户长度和版本。
+    // 返回数据布局见 encode_program_config，用 decode_program_config 解码
+    // 账户: 无
+    GetConfig,
+}
+
+// GetCountString 返回数据的最大长度：u128::MAX 的十进制位数，对任何计数宽度都足够
+pub const COUNT_STRING_MAX_LEN: usize = 39;
+
+impl CounterInstruction {
+    // 指令名称，用于日志和客户端展示
+    pub fn name(&self) -> &'static str {
+        match self {
+            CounterInstruction::Initialize { .. } => "Initialize",
+            CounterInstruction::Increment => "Increment",
+            CounterInstruction::Decrement => "Decrement",
+            CounterInstruction::Relabel { .. } => "Relabel",
+            CounterInstruction::CompareAndSet { .. } => "CompareAndSet",
+            CounterInstruction::GetAuthority => "GetAuthority",
+            CounterInstruction::IncrementUntil { .. } => "IncrementUntil",
+            CounterInstruction::DecrementAll { .. } => "DecrementAll",
+            CounterInstruction::IncrementIdempotent { .. } => "IncrementIdempotent",
+            CounterInstruction::Spend { .. } => "Spend",
+            CounterInstruction::SetAuthority { .. } => "SetAuthority",
+            CounterInstruction::GetStats => "GetStats",
+            CounterInstruction::AuthDecrementBy { .. } => "AuthDecrementBy",
+            CounterInstruction::AuthSetValue { .. } => "AuthSetValue",
+            CounterInstruction::DecrementByReport { .. } => "DecrementByReport",
+            CounterInstruction::InitializeAndIncrement { .. } => "InitializeAndIncrement",
+            CounterInstruction::IncrementSequence { .. } => "IncrementSequence",
+            CounterInstruction::DecrementByAccount => "DecrementByAccount",
+            CounterInstruction::Close => "Close",
+            CounterInstruction::IncrementIfBelow { .. } => "IncrementIfBelow",
+            CounterInstruction::GetCountString => "GetCountString",
+            CounterInstruction::InitializeWithAuthority { .. } => "InitializeWithAuthority",
+            CounterInstruction::InitializeWithExpiry { .. } => "InitializeWithExpiry",
+            CounterInstruction::InitializeWithSeed { .. } => "InitializeWithSeed",
+            CounterInstruction::ListCounters => "ListCounters",
+            CounterInstruction::Swap => "Swap",
+            CounterInstruction::DecrementAndPay { .. } => "DecrementAndPay",
+            CounterInstruction::Validate => "Validate",
+            CounterInstruction::IncrementByEpoch => "IncrementByEpoch",
+            CounterInstruction::GetAndReset => "GetAndReset",
+            CounterInstruction::InitializeWithMint { .. } => "InitializeWithMint",
+            CounterInstruction::GetMint => "GetMint",
+            CounterInstruction::IncrementIfFlag { .. } => "IncrementIfFlag",
+            CounterInstruction::InitializeWithPadding { .. } => "InitializeWithPadding",
+            CounterInstruction::InitializeWithDecay { .. } => "InitializeWithDecay",
+            CounterInstruction::InitializeWithLifetimeLimit { .. } => "InitializeWithLifetimeLimit",
+            CounterInstruction::InitializeWithCooldown { .. } => "InitializeWithCooldown",
+            CounterInstruction::InitializeFromTemplate => "InitializeFromTemplate",
+            CounterInstruction::InitializeBatch { .. } => "InitializeBatch",
+            CounterInstruction::GetCountMod { .. } => "GetCountMod",
+            CounterInstruction::GetConfig => "GetConfig",
+        }
+    }
+
+    // 是否会修改账户状态；只读指令通过 return data 返回结果
+    pub fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            CounterInstruction::GetAuthority
+                | CounterInstruction::GetStats
+                | CounterInstruction::GetCountString
+                | CounterInstruction::GetCountMod { .. }
+                | CounterInstruction::GetConfig
+                | CounterInstruction::ListCounters
+                | CounterInstruction::Validate
+                | CounterInstruction::GetMint
+        )
+    }
+
+    // 指令接受的账户数量范围（含两端），与各指令注释中的账户列表一致。
+    // 初始化指令末尾可以多一个注册表账户，Increment 可以附带授权账户（见 increment_instruction_with_authority），
+    // DecrementAll 接受任意数量的计数器账户
+    pub fn account_count(&self) -> (usize, usize) {
+        match self {
+            CounterInstruction::Initialize { .. }
+            | CounterInstruction::InitializeAndIncrement { .. }
+            | CounterInstruction::InitializeWithExpiry { .. }
+            | CounterInstruction::InitializeWithMint { .. }
+            | CounterInstruction::InitializeWithPadding { .. }
+            | CounterInstruction::InitializeWithDecay { .. }
+            | CounterInstruction::InitializeWithLifetimeLimit { .. }
+            | CounterInstruction::InitializeWithCooldown { .. }
+            | CounterInstruction::InitializeFromTemplate => (2, 3),
+            CounterInstruction::InitializeWithAuthority { .. } => (1, 2),
+            CounterInstruction::InitializeWithSeed { .. } => (3, 4),
+            CounterInstruction::Increment => (1, 2),
+            CounterInstruction::Decrement
+            | CounterInstruction::IncrementUntil { .. }
+            | CounterInstruction::Spend { .. }
+            | CounterInstruction::DecrementByReport { .. }
+            | CounterInstruction::IncrementSequence { .. }
+            | CounterInstruction::IncrementIfBelow { .. }
+            | CounterInstruction::IncrementByEpoch
+            | CounterInstruction::GetAuthority
+            | CounterInstruction::GetStats
+            | CounterInstruction::GetCountString
+            | CounterInstruction::GetCountMod { .. }
+            | CounterInstruction::ListCounters
+            | CounterInstruction::Validate
+            | CounterInstruction::GetMint => (1, 1),
+            CounterInstruction::Relabel { .. }
+            | CounterInstruction::CompareAndSet { .. }
+            | CounterInstruction::SetAuthority { .. }
+            | CounterInstruction::AuthDecrementBy { .. }
+            | CounterInstruction::AuthSetValue { .. }
+            | CounterInstruction::GetAndReset
+            | CounterInstruction::IncrementIdempotent { .. }
+            | CounterInstruction::DecrementByAccount
+            | CounterInstruction::Swap
+            | CounterInstruction::IncrementIfFlag { .. } => (2, 2),
+            CounterInstruction::Close => (3, 3),
+            CounterInstruction::DecrementAndPay { .. } => (4, 4),
+            CounterInstruction::GetConfig => (0, 0),
+            CounterInstruction::DecrementAll { .. } => (1, usize::MAX),
+            CounterInstruction::InitializeBatch { count, .. } => (3 + *count as usize, 3 + *count as usize),
+        }
+    }
+}
+
+// 计数值可以使用的整数类型，统一提供序列化和检查算术，避免为每种宽度复制一份Counter
+pub trait CounterInt:
+    Copy + Default + PartialEq + PartialOrd + std::fmt::Debug + std::fmt::Display + From<u32>
+{
+    // 序列化后占用的字节数
+    const WIDTH: usize;
+
+    // 从长度为WIDTH的小端字节切片读取
+    fn from_le_bytes(src: &[u8]) -> Self;
+    // 以小端写入长度为WIDTH的切片
+    fn to_le_bytes(self, dst: &mut [u8]);
+    // 从长度为WIDTH的大端字节切片读取
+    fn from_be_bytes(src: &[u8]) -> Self;
+    // 以大端写入长度为WIDTH的切片
+    fn to_be_bytes(self, dst: &mut [u8]);
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    // 减去rhs，不够减时得到0；rhs超出本类型范围时同样得到0
+    fn saturating_sub_u128(self, rhs: u128) -> Self;
+    // 除以u32的余数，余数一定小于divisor；divisor 为 0 时返回None
+    fn checked_rem_u32(self, divisor: u32) -> Option<u32>;
+    // 转换为i64，超出范围时返回None
+    fn to_i64(self) -> Option<i64>;
+}
+
+macro_rules! impl_counter_int {
+    ($($t:ty),*) => {
+        $(
+            impl CounterInt for $t {
+                const WIDTH: usize = std::mem::size_of::<$t>();
+
+                fn from_le_bytes(src: &[u8]) -> Self {
+                    let mut bytes = [0u8; std::mem::size_of::<$t>()];
+                    bytes.copy_from_slice(src);
+                    <$t>::from_le_bytes(bytes)
+                }
+
+                fn to_le_bytes(self, dst: &mut [u8]) {
+                    dst.copy_from_slice(&<$t>::to_le_bytes(self));
+                }
+
+                fn from_be_bytes(src: &[u8]) -> Self {
+                    let mut bytes = [0u8; std::mem::size_of::<$t>()];
+                    bytes.copy_from_slice(src);
+                    <$t>::from_be_bytes(bytes)
+                }
+
+                fn to_be_bytes(self, dst: &mut [u8]) {
+                    dst.copy_from_slice(&<$t>::to_be_bytes(self));
+                }
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+
+                fn saturating_sub_u128(self, rhs: u128) -> Self {
+                    <$t>::try_from(rhs).map_or(0, |rhs| <$t>::saturating_sub(self, rhs))
+                }
+
+                fn checked_rem_u32(self, divisor: u32) -> Option<u32> {
+                    <$t>::checked_rem(self, divisor as $t).map(|remainder| remainder as u32)
+                }
+
+                fn to_i64(self) -> Option<i64> {
+                    i64::try_from(self).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_counter_int!(u32, u64, u128);
+
+// 将计数值安全地转换为i64，超出范围时返回 ValueTooLarge 而不是截断
+pub fn count_to_i64<T: CounterInt>(count: T) -> Result<i64, CounterError> {
+    count.to_i64().ok_or(CounterError::ValueTooLarge)
+}
+
+// 程序使用的计数值类型，默认u32；启用 `u128-count` 特性后切换为u128（16字节）
+#[cfg(not(feature = "u128-count"))]
+pub type Count = u32;
+#[cfg(feature = "u128-count")]
+pub type Count = u128;
+
+// 账户数据布局版本，写在账户的第二个字节
+pub const LAYOUT_VERSION: u8 = 9;
+
+// 大端布局在版本字节上设置的标志位
+pub const BIG_ENDIAN_FLAG: u8 = 0x80;
+
+// 本次编译写入账户的版本字节。启用 `big-endian` 特性后计数值按大端存储，
+// 版本字节同时带上 BIG_ENDIAN_FLAG，小端和大端程序因此都会拒绝对方写入的账户，
+// 而不会把同一段字节按错误的字节序解读
+#[cfg(not(feature = "big-endian"))]
+pub const ACCOUNT_VERSION: u8 = LAYOUT_VERSION;
+#[cfg(feature = "big-endian")]
+pub const ACCOUNT_VERSION: u8 = LAYOUT_VERSION | BIG_ENDIAN_FLAG;
+
+// 按本次编译选择的字节序读写计数值
+#[cfg(not(feature = "big-endian"))]
+fn read_count<T: CounterInt>(src: &[u8]) -> T {
+    T::from_le_bytes(src)
+}
+#[cfg(feature = "big-endian")]
+fn read_count<T: CounterInt>(src: &[u8]) -> T {
+    T::from_be_bytes(src)
+}
+
+#[cfg(not(feature = "big-endian"))]
+fn write_count<T: CounterInt>(count: T, dst: &mut [u8]) {
+    count.to_le_bytes(dst)
+}
+#[cfg(feature = "big-endian")]
+fn write_count<T: CounterInt>(count: T, dst: &mut [u8]) {
+    count.to_be_bytes(dst)
+}
+
+// 账户数据末尾的校验和：之前所有字节的异或
+fn xor_checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |acc, byte| acc ^ byte)
+}
+
+// 计数器的第一个字节只能是0或1。其他类型的账户以各自的 discriminator 开头（例如 REGISTRY_DISCRIMINATOR），
+// 在这里被拒绝，不会因为长度和校验和恰好相符而被当作计数器读写
+fn unpack_is_initialized(byte: u8) -> Result<bool, ProgramError> {
+    match byte {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+// 最初的账户布局：1 byte for is_initialized + 4 bytes for a little-endian u32 count，
+// 没有版本、授权账户、标签和校验和
+pub const LEGACY_COUNTER_LEN: usize = 5;
+
+// 读取旧布局的账户并转换为当前结构：计数扩展到当前宽度，授权账户和标签为空。
+// 只用于读取；账户空间不足以写入新布局，写入仍会失败
+fn unpack_legacy<T: CounterInt>(src: &[u8]) -> Result<Counter<T>, ProgramError> {
+    let (&is_initialized, count) = src.split_first().ok_or(ProgramError::InvalidAccountData)?;
+    let count: [u8; 4] = count.try_into().map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(Counter {
+        is_initialized: unpack_is_initialized(is_initialized)?,
+        count: T::from(u32::from_le_bytes(count)),
+        authority: Pubkey::default(),
+        label: [0u8; LABEL_LEN],
+        expires_at: 0,
+        total_ops: 0,
+        mint: Pubkey::default(),
+        decay_per_slot: 0,
+        last_slot: 0,
+        lifetime_limit: 0,
+        min_seconds_between: 0,
+        last_ts: 0,
+        in_progress: false,
+    })
+}
+
+// 定义计数器状态结构
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Counter<T: CounterInt = Count> {
+    pub is_initialized: bool,
+    pub count: T,
+    // 有权执行管理操作（如修改标签）的账户
+    pub authority: Pubkey,
+    // 便于人工识别的标签，UTF-8编码，尾部补0
+    pub label: [u8; LABEL_LEN],
+    // 过期时间（unix时间戳，秒），之后除 Close 外的所有修改都返回 CounterError::Expired；0 表示永不过期
+    pub expires_at: i64,
+    // 成功修改账户的累计次数（初始化也算一次），计数一加一减回到原值时也能看出有过操作
+    pub total_ops: u64,
+    // 关联的SPL代币mint，全零表示未关联；只在初始化时设置
+    pub mint: Pubkey,
+    // 每经过一个slot自动减少的数量，0 表示不衰减；只在初始化时设置
+    pub decay_per_slot: u32,
+    // 上次结算衰减时的slot，见 settle
+    pub last_slot: u64,
+    // 初始化之后最多允许的修改次数，达到后拒绝自增，见 check_increment_allowed；0 表示不限制，只在初始化时设置
+    pub lifetime_limit: u64,
+    // 两次自增之间的最短间隔（秒），0 表示不限制；只在初始化时设置
+    pub min_seconds_between: u32,
+    // 上次自增的unix时间戳，只在 min_seconds_between 非零时记录
+    pub last_ts: i64,
+    // 重入保护：修改计数器的指令在CPI期间置位，结束时清除，见 write_counter
+    pub in_progress: bool,
+}
+
+impl<T: CounterInt> Counter<T> {
+    // 已初始化、计数为count的计数器，其余字段为默认值：授权人和标签为全零，不过期、不衰减、不限制自增。
+    // 可选配置用下面的 with_* 方法设置，例如 Counter::new(0).with_authority(authority).with_label(label)
+    pub fn new(count: T) -> Self {
+        Counter { is_initialized: true, count, ..Counter::default() }
+    }
+
+    pub fn with_authority(mut self, authority: Pubkey) -> Self {
+        self.authority = authority;
+        self
+    }
+
+    pub fn with_label(mut self, label: [u8; LABEL_LEN]) -> Self {
+        self.label = label;
+        self
+    }
+
+    // 过期时间（unix时间戳），0 表示永不过期
+    pub fn with_expiry(mut self, expires_at: i64) -> Self {
+        self.expires_at = expires_at;
+        self
+    }
+
+    pub fn with_mint(mut self, mint: Pubkey) -> Self {
+        self.mint = mint;
+        self
+    }
+
+    // 每个slot衰减 decay_per_slot，从 last_slot 开始结算
+    pub fn with_decay(mut self, decay_per_slot: u32, last_slot: u64) -> Self {
+        self.decay_per_slot = decay_per_slot;
+        self.last_slot = last_slot;
+        self
+    }
+
+    pub fn with_lifetime_limit(mut self, lifetime_limit: u64) -> Self {
+        self.lifetime_limit = lifetime_limit;
+        self
+    }
+
+    // 两次自增之间至少间隔 min_seconds_between 秒
+    pub fn with_cooldown(mut self, min_seconds_between: u32) -> Self {
+        self.min_seconds_between = min_seconds_between;
+        self
+    }
+
+    // 与 Pack::unpack 相同，但允许账户数据比 LEN 长（客户端多分配了空间），只读取前 LEN 字节；
+    // 需要拒绝多余数据时使用严格的 Pack::unpack
+    pub fn unpack_lenient(src: &[u8]) -> Result<Self, ProgramError> {
+        let counter = Self::unpack_lenient_unchecked(src)?;
+        if !counter.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(counter)
+    }
+
+    // 与 Pack::unpack_unchecked 相同，但允许多余的尾部数据
+    pub fn unpack_lenient_unchecked(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::unpack_from_slice(src)
+    }
+
+    // 结算从 last_slot 到 now_slot 之间的衰减：count 减去 decay_per_slot * 经过的slot数，最低为0。
+    // 分多次结算与一次结算的结果相同，因此只读指令结算后不写回也不会影响之后的结果。
+    // decay_per_slot 为 0 时不做任何修改
+    pub fn settle(&mut self, now_slot: u64) {
+        if self.decay_per_slot == 0 {
+            return;
+        }
+        let elapsed = now_slot.saturating_sub(self.last_slot);
+        self.count = math::sub_floor(self.count, self.decay_per_slot as u128 * elapsed as u128);
+        self.last_slot = self.last_slot.max(now_slot);
+    }
+}
+
+// 实现Pack trait以便序列化和反序列化
+impl<T: CounterInt> Sealed for Counter<T> {}
+
+impl<T: CounterInt> IsInitialized for Counter<T> {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl<T: CounterInt> Pack for Counter<T> {
+    // 1 byte for is_initialized + 1 byte for layout version + T::WIDTH bytes for count
+    // + 32 bytes for authority + 32 bytes for label + 8 bytes for expires_at + 8 bytes for total_ops
+    // + 32 bytes for mint + 4 bytes for decay_per_slot + 8 bytes for last_slot + 8 bytes for lifetime_limit
+    // + 4 bytes for min_seconds_between + 8 bytes for last_ts + 1 byte for in_progress + 1 byte for checksum
+    const LEN: usize = 1 + 1 + T::WIDTH + 32 + LABEL_LEN + 8 + 8 + 32 + 4 + 8 + 8 + 4 + 8 + 1 + 1;
+
+    // 只读取前 LEN 字节，之后多余的数据被忽略；Pack::unpack 自身仍要求长度严格等于 LEN。
+    // 长度恰好为 LEGACY_COUNTER_LEN 的账户按最初的布局读取
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() == LEGACY_COUNTER_LEN {
+            return unpack_legacy(src);
+        }
+        if src.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // 全零的新账户校验和也是0，不需要特殊处理
+        let (body, checksum) = src[..Self::LEN].split_at(Self::LEN - 1);
+        if checksum[0] != xor_checksum(body) {
+            return Err(CounterError::CorruptData.into());
+        }
+
+        let (is_initialized, rest) = body.split_at(1);
+        let (version, rest) = rest.split_at(1);
+        let (count, rest) = rest.split_at(T::WIDTH);
+
+        // 已初始化的账户必须是当前布局和字节序写入的；全零的新账户还没有版本
+        let is_initialized = unpack_is_initialized(is_initialized[0])?;
+        if is_initialized && version[0] != ACCOUNT_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (authority, rest) = rest.split_at(32);
+        let (label_bytes, rest) = rest.split_at(LABEL_LEN);
+        let (expires_at, rest) = rest.split_at(8);
+        let (total_ops, rest) = rest.split_at(8);
+        let (mint, rest) = rest.split_at(32);
+        let (decay_per_slot, rest) = rest.split_at(4);
+        let (last_slot, rest) = rest.split_at(8);
+        let (lifetime_limit, rest) = rest.split_at(8);
+        let (min_seconds_between, rest) = rest.split_at(4);
+        let (last_ts, in_progress) = rest.split_at(8);
+
+        let mut label = [0u8; LABEL_LEN];
+        label.copy_from_slice(label_bytes);
+        let mut expires_at_bytes = [0u8; 8];
+        expires_at_bytes.copy_from_slice(expires_at);
+        let mut total_ops_bytes = [0u8; 8];
+        total_ops_bytes.copy_from_slice(total_ops);
+        let mut decay_per_slot_bytes = [0u8; 4];
+        decay_per_slot_bytes.copy_from_slice(decay_per_slot);
+        let mut last_slot_bytes = [0u8; 8];
+        last_slot_bytes.copy_from_slice(last_slot);
+        let mut lifetime_limit_bytes = [0u8; 8];
+        lifetime_limit_bytes.copy_from_slice(lifetime_limit);
+        let mut min_seconds_between_bytes = [0u8; 4];
+        min_seconds_between_bytes.copy_from_slice(min_seconds_between);
+        let mut last_ts_bytes = [0u8; 8];
+        last_ts_bytes.copy_from_slice(last_ts);
+
+        Ok(Counter {
+            is_initialized,
+            count: read_count(count),
+            authority: Pubkey::try_from(authority).map_err(|_| ProgramError::InvalidAccountData)?,
+            label,
+            expires_at: i64::from_le_bytes(expires_at_bytes),
+            total_ops: u64::from_le_bytes(total_ops_bytes),
+            mint: Pubkey::try_from(mint).map_err(|_| ProgramError::InvalidAccountData)?,
+            decay_per_slot: u32::from_le_bytes(decay_per_slot_bytes),
+            last_slot: u64::from_le_bytes(last_slot_bytes),
+            lifetime_limit: u64::from_le_bytes(lifetime_limit_bytes),
+            min_seconds_between: u32::from_le_bytes(min_seconds_between_bytes),
+            last_ts: i64::from_le_bytes(last_ts_bytes),
+            in_progress: in_progress[0] != 0,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let (body, checksum) = dst.split_at_mut(Self::LEN - 1);
+        let (is_initialized, rest) = body.split_at_mut(1);
+        let (version, rest) = rest.split_at_mut(1);
+        let (count, rest) = rest.split_at_mut(T::WIDTH);
+        let (authority, rest) = rest.split_at_mut(32);
+        let (label, rest) = rest.split_at_mut(LABEL_LEN);
+        let (expires_at, rest) = rest.split_at_mut(8);
+        let (total_ops, rest) = rest.split_at_mut(8);
+        let (mint, rest) = rest.split_at_mut(32);
+        let (decay_per_slot, rest) = rest.split_at_mut(4);
+        let (last_slot, rest) = rest.split_at_mut(8);
+        let (lifetime_limit, rest) = rest.split_at_mut(8);
+        let (min_seconds_between, rest) = rest.split_at_mut(4);
+        let (last_ts, in_progress) = rest.split_at_mut(8);
+
+        is_initialized[0] = self.is_initialized as u8;
+        version[0] = ACCOUNT_VERSION;
+        write_count(self.count, count);
+        authority.copy_from_slice(self.authority.as_ref());
+        label.copy_from_slice(&self.label);
+        expires_at.copy_from_slice(&self.expires_at.to_le_bytes());
+        total_ops.copy_from_slice(&self.total_ops.to_le_bytes());
+        mint.copy_from_slice(self.mint.as_ref());
+        decay_per_slot.copy_from_slice(&self.decay_per_slot.to_le_bytes());
+        last_slot.copy_from_slice(&self.last_slot.to_le_bytes());
+        lifetime_limit.copy_from_slice(&self.lifetime_limit.to_le_bytes());
+        min_seconds_between.copy_from_slice(&self.min_seconds_between.to_le_bytes());
+        last_ts.copy_from_slice(&self.last_ts.to_le_bytes());
+        in_progress[0] = self.in_progress as u8;
+        checksum[0] = xor_checksum(body);
+    }
+}
+
+// 幂等键缓存：记录某个计数器最近处理过的客户端重试键（环形缓冲区）。
+// 已初始化的缓存以 IDEMPOTENCY_CACHE_DISCRIMINATOR 开头；未初始化的缓存必须全为零
+#[derive(Debug, Default, PartialEq)]
+pub struct IdempotencyCache {
+    pub is_initialized: bool,
+    // 缓存所属的计数器账户
+    pub counter: Pubkey,
+    // 下一个写入位置
+    pub next: u8,
+    // 已写入的键数量，最多 IDEMPOTENCY_CACHE_SIZE
+    pub len: u8,
+    pub keys: [u64; IDEMPOTENCY_CACHE_SIZE],
+}
+
+impl IdempotencyCache {
+    // 键是否已经记录过；len 超出缓存大小时返回 InvalidAccountData 而不是panic
+    pub fn contains(&self, key: u64) -> Result<bool, ProgramError> {
+        let keys = self.keys.get(..self.len as usize).ok_or(ProgramError::InvalidAccountData)?;
+        Ok(keys.contains(&key))
+    }
+
+    // 记录一个键，缓存满时覆盖最早的键；next 超出缓存大小时返回 InvalidAccountData
+    pub fn record(&mut self, key: u64) -> Result<(), ProgramError> {
+        *self.keys.get_mut(self.next as usize).ok_or(ProgramError::InvalidAccountData)? = key;
+        self.next = ((self.next as usize + 1) % IDEMPOTENCY_CACHE_SIZE) as u8;
+        if (self.len as usize) < IDEMPOTENCY_CACHE_SIZE {
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+impl Sealed for IdempotencyCache {}
+
+impl IsInitialized for IdempotencyCache {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for IdempotencyCache {
+    // 1 byte for the discriminator + 32 bytes for counter + 1 byte for next + 1 byte for len
+    // + 8 bytes per key
+    const LEN: usize = 1 + 32 + 1 + 1 + 8 * IDEMPOTENCY_CACHE_SIZE;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (discriminator, rest) = src.split_at(1);
+        let (counter, rest) = rest.split_at(32);
+        let (next, rest) = rest.split_at(1);
+        let (len, rest) = rest.split_at(1);
+
+        // 其他类型的账户（例如长度相同的计数器）在这里被拒绝
+        let is_initialized = match discriminator[0] {
+            IDEMPOTENCY_CACHE_DISCRIMINATOR => true,
+            0 if src.iter().all(|&byte| byte == 0) => false,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if next[0] as usize >= IDEMPOTENCY_CACHE_SIZE || len[0] as usize > IDEMPOTENCY_CACHE_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut keys = [0u64; IDEMPOTENCY_CACHE_SIZE];
+        for (key, bytes) in keys.iter_mut().zip(rest.chunks_exact(8)) {
+            *key = u64::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]]);
+        }
+
+        Ok(IdempotencyCache {
+            is_initialized,
+            counter: Pubkey::try_from(counter).map_err(|_| ProgramError::InvalidAccountData)?,
+            next: next[0],
+            len: len[0],
+            keys,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let (discriminator, rest) = dst.split_at_mut(1);
+        let (counter, rest) = rest.split_at_mut(32);
+        let (next, rest) = rest.split_at_mut(1);
+        let (len, rest) = rest.split_at_mut(1);
+
+        discriminator[0] = if self.is_initialized { IDEMPOTENCY_CACHE_DISCRIMINATOR } else { 0 };
+        counter.copy_from_slice(self.counter.as_ref());
+        next[0] = self.next;
+        len[0] = self.len;
+        for (key, bytes) in self.keys.iter().zip(rest.chunks_exact_mut(8)) {
+            bytes.copy_from_slice(&key.to_le_bytes());
+        }
+    }
+}
+
+// Validate 指令返回的账户状态
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountStatus {
+    // 当前布局、校验和正确并且已初始化
+    Valid = 0,
+    // 旧布局或另一种字节序写入的账户
+    WrongVersion = 1,
+    // 长度不足或校验和不符
+    Corrupt = 2,
+    // 当前布局的全新账户，尚未初始化
+    Uninitialized = 3,
+}
+
+// 检查账户数据的格式，不解析具体字段；供 Validate 指令和需要预检账户的客户端使用
+pub fn validate_counter_data(data: &[u8]) -> AccountStatus {
+    // 最初的5字节布局仍然可以读取，但不是当前格式
+    if data.len() == LEGACY_COUNTER_LEN {
+        return AccountStatus::WrongVersion;
+    }
+    // 旧版本的账户长度也不同，先看版本字节，避免把它们误报为损坏
+    if let [is_initialized, version, ..] = data {
+        if *is_initialized != 0 && *version != ACCOUNT_VERSION {
+            return AccountStatus::WrongVersion;
+        }
+    }
+    let Some(data) = data.get(..<Counter>::LEN) else {
+        return AccountStatus::Corrupt;
+    };
+    let (body, checksum) = data.split_at(<Counter>::LEN - 1);
+    if checksum[0] != xor_checksum(body) || unpack_is_initialized(body[0]).is_err() {
+        return AccountStatus::Corrupt;
+    }
+    if body[0] == 0 {
+        return AccountStatus::Uninitialized;
+    }
+    AccountStatus::Valid
+}
+
+// 计数器注册表：记录通过本程序初始化的计数器，客户端无需索引器即可枚举所有计数器。
+// 已初始化的注册表以 REGISTRY_DISCRIMINATOR 开头；未初始化的注册表必须全为零
+#[derive(Debug, PartialEq)]
+pub struct Registry {
+    pub is_initialized: bool,
+    // 已记录的计数器数量，最多 REGISTRY_CAPACITY
+    pub len: u8,
+    pub counters: [Pubkey; REGISTRY_CAPACITY],
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry {
+            is_initialized: false,
+            len: 0,
+            counters: [Pubkey::default(); REGISTRY_CAPACITY],
+        }
+    }
+}
+
+impl Registry {
+    // 已记录的计数器；len 超出容量时返回 InvalidAccountData 而不是panic
+    pub fn counters(&self) -> Result<&[Pubkey], ProgramError> {
+        self.counters.get(..self.len as usize).ok_or(ProgramError::InvalidAccountData)
+    }
+
+    // 追加一个计数器，注册表已满时返回 RegistryFull
+    pub fn append(&mut self, counter: Pubkey) -> Result<(), ProgramError> {
+        let slot = self.counters.get_mut(self.len as usize).ok_or(CounterError::RegistryFull)?;
+        *slot = counter;
+        self.len += 1;
+        Ok(())
+    }
+}
+
+impl Sealed for Registry {}
+
+impl IsInitialized for Registry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Registry {
+    // 1 byte for the discriminator + 1 byte for len + 32 bytes per counter
+    const LEN: usize = 1 + 1 + 32 * REGISTRY_CAPACITY;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (discriminator, rest) = src.split_at(1);
+        let (len, rest) = rest.split_at(1);
+
+        // 其他类型的账户（例如填充后恰好同样长的计数器）在这里被拒绝
+        let is_initialized = match discriminator[0] {
+            REGISTRY_DISCRIMINATOR => true,
+            0 if src.iter().all(|&byte| byte == 0) => false,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if len[0] as usize > REGISTRY_CAPACITY {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut counters = [Pubkey::default(); REGISTRY_CAPACITY];
+        for (counter, bytes) in counters.iter_mut().zip(rest.chunks_exact(32)) {
+            *counter = Pubkey::try_from(bytes).map_err(|_| ProgramError::InvalidAccountData)?;
+        }
+
+        Ok(Registry {
+            is_initialized,
+            len: len[0],
+            counters,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let (discriminator, rest) = dst.split_at_mut(1);
+        let (len, rest) = rest.split_at_mut(1);
+
+        discriminator[0] = if self.is_initialized { REGISTRY_DISCRIMINATOR } else { 0 };
+        len[0] = self.len;
+        for (counter, bytes) in self.counters.iter().zip(rest.chunks_exact_mut(32)) {
+            bytes.copy_from_slice(counter.as_ref());
+        }
+    }
+}
+
+// 将字符串编码为定长标签，超过 LABEL_LEN 字节时返回错误
+pub fn encode_label(label: &str) -> Result<[u8; LABEL_LEN], ProgramError> {
+    unpack_label(label.as_bytes())
+}
+
+// 将定长标签解码为去掉尾部补0的字符串
+pub fn decode_label(label: &[u8; LABEL_LEN]) -> String {
+    let end = label.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    String::from_utf8_lossy(&label[..end]).into_owned()
+}
+
+// 从指令数据中读取标签：最多 LABEL_LEN 字节的UTF-8，不足部分补0
+fn unpack_label(src: &[u8]) -> Result<[u8; LABEL_LEN], ProgramError> {
+    if src.len() > LABEL_LEN || std::str::from_utf8(src).is_err() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut label = [0u8; LABEL_LEN];
+    label[..src.len()].copy_from_slice(src);
+    Ok(label)
+}
+
+// 从指令数据中读取带单字节长度前缀的种子，返回剩余部分
+fn unpack_seed(src: &[u8]) -> Result<(String, &[u8]), ProgramError> {
+    let (&len, rest) = src.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+    let len = len as usize;
+    if len > MAX_SEED_LEN || rest.len() < len {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (seed, rest) = rest.split_at(len);
+    let seed = std::str::from_utf8(seed).map_err(|_| ProgramError::InvalidInstructionData)?;
+    Ok((seed.to_string(), rest))
+}
+
+// 从指令数据中读取32字节的公钥
+fn unpack_pubkey(src: &[u8]) -> Result<Pubkey, ProgramError> {
+    let bytes = src.get(..32).ok_or(ProgramError::InvalidInstructionData)?;
+    Pubkey::try_from(bytes).map_err(|_| ProgramError::InvalidInstructionData)
+}
+
+// 从指令数据中读取一个小端u16，返回剩余部分
+fn unpack_u16(src: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+    if src.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (value, rest) = src.split_at(2);
+    Ok((u16::from_le_bytes([value[0], value[1]]), rest))
+}
+
+// 从指令数据中读取一个小端u32，返回剩余部分
+fn unpack_u32(src: &[u8]) -> Result<(u32, &[u8]), ProgramError> {
+    if src.len() < 4 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (value, rest) = src.split_at(4);
+    Ok((u32::from_le_bytes([value[0], value[1], value[2], value[3]]), rest))
+}
+
+// 从指令数据中读取一个小端u64，返回剩余部分
+fn unpack_u64(src: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    if src.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (value, rest) = src.split_at(8);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(value);
+    Ok((u64::from_le_bytes(bytes), rest))
+}
+
+// 指令数据第一个字节是编码格式版本，之后的内容由对应版本的解析器处理
+pub const INSTRUCTION_FORMAT_VERSION: u8 = 0;
+
+// 严格格式：操作码和参数布局与 v0 完全相同，但指令数据必须恰好等于 pack_instruction_data 的输出。
+// v0 为兼容已发布的客户端会忽略参数之后的多余字节，这样客户端的编码错误（例如多写了参数、
+// 用错了操作码）可能被悄悄接受；严格格式下任何多余的尾部字节都会被拒绝，
+// 比如不带参数的 Increment 后面多出一个字节就会失败。标签也必须补齐到 LABEL_LEN 字节。
+pub const STRICT_INSTRUCTION_FORMAT_VERSION: u8 = 1;
+
+// 解析指令数据
+pub fn unpack_instruction_data(instruction_data: &[u8]) -> Result<CounterInstruction, ProgramError> {
+    let (&version, rest) = instruction_data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    match version {
+        0 => unpack_instruction_data_v0(rest),
+        STRICT_INSTRUCTION_FORMAT_VERSION => unpack_instruction_data_strict(rest),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// 严格格式：按 v0 解析后重新编码并逐字节比较，参数之外的任何字节都会导致不一致
+fn unpack_instruction_data_strict(data: &[u8]) -> Result<CounterInstruction, ProgramError> {
+    let instruction = unpack_instruction_data_v0(data)?;
+    if pack_instruction_data(&instruction).get(1..) != Some(data) {
+        msg!("Instruction data has unexpected trailing bytes");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(instruction)
+}
+
+// v0 格式：单字节操作码，后接该指令的参数
+fn unpack_instruction_data_v0(data: &[u8]) -> Result<CounterInstruction, ProgramError> {
+    let (&tag, rest) = data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    Ok(match tag {
+        0 => CounterInstruction::Initialize { label: unpack_label(rest)? },
+        1 => CounterInstruction::Increment,
+        2 => CounterInstruction::Decrement,
+        3 => CounterInstruction::Relabel { label: unpack_label(rest)? },
+        4 => {
+            let (expected, rest) = unpack_u32(rest)?;
+            let (new, _) = unpack_u32(rest)?;
+            CounterInstruction::CompareAndSet { expected, new }
+        }
+        5 => CounterInstruction::GetAuthority,
+        6 => {
+            let (target, _) = unpack_u32(rest)?;
+            CounterInstruction::IncrementUntil { target }
+        }
+        7 => {
+            let skip_invalid = match rest.first() {
+                Some(0) => false,
+                Some(1) => true,
+                _ => return Err(ProgramError::InvalidInstructionData),
+            };
+            CounterInstruction::DecrementAll { skip_invalid }
+        }
+        8 => {
+            let (key, _) = unpack_u64(rest)?;
+            CounterInstruction::IncrementIdempotent { key }
+        }
+        9 => {
+            let (amount, _) = unpack_u32(rest)?;
+            CounterInstruction::Spend { amount }
+        }
+        10 => CounterInstruction::SetAuthority { new_authority: unpack_pubkey(rest)? },
+        11 => CounterInstruction::GetStats,
+        12 => {
+            let (amount, _) = unpack_u32(rest)?;
+            CounterInstruction::AuthDecrementBy { amount }
+        }
+        13 => {
+            let (value, _) = unpack_u32(rest)?;
+            CounterInstruction::AuthSetValue { value }
+        }
+        14 => {
+            let (amount, _) = unpack_u32(rest)?;
+            CounterInstruction::DecrementByReport { amount }
+        }
+        15 => CounterInstruction::InitializeAndIncrement { label: unpack_label(rest)? },
+        16 => {
+            let (len, mut rest) = unpack_u32(rest)?;
+            // 先按剩余数据校验个数，避免按伪造的长度分配内存
+            if rest.len() != len as usize * 4 {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            let mut amounts = Vec::with_capacity(len as usize);
+            while !rest.is_empty() {
+                let (amount, remaining) = unpack_u32(rest)?;
+                amounts.push(amount);
+                rest = remaining;
+            }
+            CounterInstruction::IncrementSequence { amounts }
+        }
+        17 => CounterInstruction::DecrementByAccount,
+        18 => CounterInstruction::Close,
+        19 => {
+            let (threshold, _) = unpack_u32(rest)?;
+            CounterInstruction::IncrementIfBelow { threshold }
+        }
+        20 => CounterInstruction::GetCountString,
+        21 => CounterInstruction::InitializeWithAuthority { authority: unpack_pubkey(rest)? },
+        22 => {
+            let (expires_at, rest) = unpack_u64(rest)?;
+            CounterInstruction::InitializeWithExpiry { expires_at: expires_at as i64, label: unpack_label(rest)? }
+        }
+        23 => {
+            let (seed, rest) = unpack_seed(rest)?;
+            CounterInstruction::InitializeWithSeed { seed, label: unpack_label(rest)? }
+        }
+        24 => CounterInstruction::ListCounters,
+        25 => CounterInstruction::Swap,
+        26 => {
+            let (amount, _) = unpack_u32(rest)?;
+            CounterInstruction::DecrementAndPay { amount }
+        }
+        27 => CounterInstruction::Validate,
+        28 => CounterInstruction::IncrementByEpoch,
+        29 => CounterInstruction::GetAndReset,
+        30 => CounterInstruction::InitializeWithMint {
+            mint: unpack_pubkey(rest)?,
+            label: unpack_label(rest.get(32..).ok_or(ProgramError::InvalidInstructionData)?)?,
+        },
+        31 => CounterInstruction::GetMint,
+        32 => {
+            let (offset, _) = unpack_u32(rest)?;
+            CounterInstruction::IncrementIfFlag { offset }
+        }
+        33 => {
+            let (extra, rest) = unpack_u16(rest)?;
+            CounterInstruction::InitializeWithPadding { extra, label: unpack_label(rest)? }
+        }
+        34 => {
+            let (decay_per_slot, rest) = unpack_u32(rest)?;
+            CounterInstruction::InitializeWithDecay { decay_per_slot, label: unpack_label(rest)? }
+        }
+        35 => {
+            let (lifetime_limit, rest) = unpack_u64(rest)?;
+            CounterInstruction::InitializeWithLifetimeLimit { lifetime_limit, label: unpack_label(rest)? }
+        }
+        36 => {
+            let (min_seconds_between, rest) = unpack_u32(rest)?;
+            CounterInstruction::InitializeWithCooldown { min_seconds_between, label: unpack_label(rest)? }
+        }
+        37 => CounterInstruction::InitializeFromTemplate,
+        38 => {
+            let (&count, rest) = rest.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+            CounterInstruction::InitializeBatch { count, label: unpack_label(rest)? }
+        }
+        39 => {
+            let (divisor, _) = unpack_u32(rest)?;
+            CounterInstruction::GetCountMod { divisor }
+        }
+        40 => CounterInstruction::GetConfig,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    })
+}
+
+// 将指令编码为指令数据，供客户端构造交易
+pub fn pack_instruction_data(instruction: &CounterInstruction) -> Vec<u8> {
+    let mut data = vec![INSTRUCTION_FORMAT_VERSION];
+    match instruction {
+        CounterInstruction::Initialize { label } => {
+            data.push(0);
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::Increment => data.push(1),
+        CounterInstruction::Decrement => data.push(2),
+        CounterInstruction::Relabel { label } => {
+            data.push(3);
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::CompareAndSet { expected, new } => {
+            data.push(4);
+            data.extend_from_slice(&expected.to_le_bytes());
+            data.extend_from_slice(&new.to_le_bytes());
+        }
+        CounterInstruction::GetAuthority => data.push(5),
+        CounterInstruction::IncrementUntil { target } => {
+            data.push(6);
+            data.extend_from_slice(&target.to_le_bytes());
+        }
+        CounterInstruction::DecrementAll { skip_invalid } => {
+            data.push(7);
+            data.push(*skip_invalid as u8);
+        }
+        CounterInstruction::IncrementIdempotent { key } => {
+            data.push(8);
+            data.extend_from_slice(&key.to_le_bytes());
+        }
+        CounterInstruction::Spend { amount } => {
+            data.push(9);
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        CounterInstruction::SetAuthority { new_authority } => {
+            data.push(10);
+            data.extend_from_slice(new_authority.as_ref());
+        }
+        CounterInstruction::GetStats => data.push(11),
+        CounterInstruction::AuthDecrementBy { amount } => {
+            data.push(12);
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        CounterInstruction::AuthSetValue { value } => {
+            data.push(13);
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        CounterInstruction::DecrementByReport { amount } => {
+            data.push(14);
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        CounterInstruction::InitializeAndIncrement { label } => {
+            data.push(15);
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::IncrementSequence { amounts } => {
+            data.push(16);
+            data.extend_from_slice(&(amounts.len() as u32).to_le_bytes());
+            for amount in amounts {
+                data.extend_from_slice(&amount.to_le_bytes());
+            }
+        }
+        CounterInstruction::DecrementByAccount => data.push(17),
+        CounterInstruction::Close => data.push(18),
+        CounterInstruction::IncrementIfBelow { threshold } => {
+            data.push(19);
+            data.extend_from_slice(&threshold.to_le_bytes());
+        }
+        CounterInstruction::GetCountString => data.push(20),
+        CounterInstruction::InitializeWithAuthority { authority } => {
+            data.push(21);
+            data.extend_from_slice(authority.as_ref());
+        }
+        CounterInstruction::InitializeWithExpiry { expires_at, label } => {
+            data.push(22);
+            data.extend_from_slice(&expires_at.to_le_bytes());
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::InitializeWithSeed { seed, label } => {
+            data.push(23);
+            data.push(seed.len() as u8);
+            data.extend_from_slice(seed.as_bytes());
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::ListCounters => data.push(24),
+        CounterInstruction::Swap => data.push(25),
+        CounterInstruction::DecrementAndPay { amount } => {
+            data.push(26);
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        CounterInstruction::Validate => data.push(27),
+        CounterInstruction::IncrementByEpoch => data.push(28),
+        CounterInstruction::GetAndReset => data.push(29),
+        CounterInstruction::InitializeWithMint { mint, label } => {
+            data.push(30);
+            data.extend_from_slice(mint.as_ref());
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::GetMint => data.push(31),
+        CounterInstruction::IncrementIfFlag { offset } => {
+            data.push(32);
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        CounterInstruction::InitializeWithPadding { extra, label } => {
+            data.push(33);
+            data.extend_from_slice(&extra.to_le_bytes());
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::InitializeWithDecay { decay_per_slot, label } => {
+            data.push(34);
+            data.extend_from_slice(&decay_per_slot.to_le_bytes());
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::InitializeWithLifetimeLimit { lifetime_limit, label } => {
+            data.push(35);
+            data.extend_from_slice(&lifetime_limit.to_le_bytes());
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::InitializeWithCooldown { min_seconds_between, label } => {
+            data.push(36);
+            data.extend_from_slice(&min_seconds_between.to_le_bytes());
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::InitializeFromTemplate => data.push(37),
+        CounterInstruction::InitializeBatch { count, label } => {
+            data.push(38);
+            data.push(*count);
+            data.extend_from_slice(label);
+        }
+        CounterInstruction::GetCountMod { divisor } => {
+            data.push(39);
+            data.extend_from_slice(&divisor.to_le_bytes());
+        }
+        CounterInstruction::GetConfig => data.push(40),
+    }
+    data
+}
+
+// 按严格格式编码指令数据，程序会拒绝多余的尾部字节
+pub fn pack_instruction_data_strict(instruction: &CounterInstruction) -> Vec<u8> {
+    let mut data = pack_instruction_data(instruction);
+    data[0] = STRICT_INSTRUCTION_FORMAT_VERSION;
+    data
+}
+
+// 预留 padding 字节时计数器账户需要分配的空间
+pub fn counter_account_space(padding: u16) -> usize {
+    <Counter>::LEN + padding as usize
+}
+
+// 构造需要授权账户签名的指令，账户顺序与处理函数读取的顺序一致：
+// [可写] 计数器账户, [签名] 授权账户
+pub fn authority_instruction(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    instruction: &CounterInstruction,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*counter, false), AccountMeta::new_readonly(*authority, true)],
+        data: pack_instruction_data(instruction),
+    }
+}
+
+// 构造 InitializeWithSeed 指令，counter 应为 Pubkey::create_with_seed(base, seed, program_id) 的结果；
+// 账户顺序: [可写] 计数器账户, [签名] 授权账户, [签名] base账户
+pub fn initialize_with_seed_instruction(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    base: &Pubkey,
+    seed: &str,
+    label: [u8; LABEL_LEN],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*base, true),
+        ],
+        data: pack_instruction_data(&CounterInstruction::InitializeWithSeed { seed: seed.to_string(), label }),
+    }
+}
+
+// 构造 Close 指令，计数器的全部lamports转给destination；
+// 账户顺序: [可写] 计数器账户, [签名] 授权账户, [可写] 接收lamports的账户
+pub fn close_instruction(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*destination, false),
+        ],
+        data: pack_instruction_data(&CounterInstruction::Close),
+    }
+}
+
+// InitializeBatch 创建的第index个计数器的地址和bump
+pub fn batch_counter_address(program_id: &Pubkey, base: &Pubkey, index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BATCH_COUNTER_SEED, base.as_ref(), &[index]], program_id)
+}
+
+// 构造 InitializeBatch 指令，计数器地址由 batch_counter_address 派生
+pub fn initialize_batch_instruction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    base: &Pubkey,
+    count: u8,
+    label: [u8; LABEL_LEN],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(*base, true),
+        AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+    ];
+    accounts.extend((0..count).map(|index| AccountMeta::new(batch_counter_address(program_id, base, index).0, false)));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: pack_instruction_data(&CounterInstruction::InitializeBatch { count, label }),
+    }
+}
+
+// 构造带授权账户签名的 Increment 指令；附带授权账户时处理函数会校验它是计数器记录的authority并且签名
+pub fn increment_instruction_with_authority(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    authority_instruction(program_id, counter, authority, &CounterInstruction::Increment)
+}
+
+// 解码base64编码的账户数据（JSON RPC和导出的账户快照使用的格式），无效时返回 ProgramError::InvalidArgument
+#[cfg(feature = "client")]
+pub fn decode_account_base64(s: &str) -> Result<Vec<u8>, ProgramError> {
+    use base64::{prelude::BASE64_STANDARD, Engine};
+
+    BASE64_STANDARD.decode(s).map_err(|_| ProgramError::InvalidArgument)
+}
+
+// 解码JSON RPC返回的base64账户数据。base64本身无效时返回 ProgramError::InvalidArgument，
+// 与 Counter::unpack 返回的账户数据错误区分开
+#[cfg(feature = "client")]
+pub fn decode_counter_base64(s: &str) -> Result<Counter, ProgramError> {
+    Counter::unpack(&decode_account_base64(s)?)
+}
+
+// 将计数器状态写回账户
+// 读取时的状态original带有 in_progress 标志时返回 CounterError::Reentrancy：另一个修改这个计数器的指令
+// 正在进行CPI，此时写入会被它结束时的写入覆盖。标志因故残留时只能 Close 账户。
+// 与original相同时直接返回，不打包也不写入，节省计算量且不会把账户标记为已修改。
+// 否则累加 total_ops 后写入账户
+fn write_counter(original: &Counter, mut counter: Counter, account: &AccountInfo) -> ProgramResult {
+    if original.in_progress {
+        msg!("Counter {} is already being modified", account.key);
+        return Err(CounterError::Reentrancy.into());
+    }
+    if *original == counter {
+        verbose_msg!("Counter unchanged, skipping write");
+        return Ok(());
+    }
+    counter.total_ops = math::add(original.total_ops, 1)?;
+    trace!("pack", "counter={} count={} -> {} total_ops={}", account.key, original.count, counter.count,
+        counter.total_ops);
+    store_counter(counter, account)
+}
+
+// 原样写入计数器状态，不检查也不修改 total_ops。
+// 先打包到本地缓冲区，打包完全成功后再一次性拷贝进账户数据，
+// 避免打包中途出错或panic时在账户里留下写了一半的数据
+fn store_counter(counter: Counter, account: &AccountInfo) -> ProgramResult {
+    let mut buffer = [0u8; <Counter>::LEN];
+    Counter::pack(counter, &mut buffer)?;
+
+    // 账户可能比 LEN 大，只覆盖前 LEN 字节
+    let mut data = account.data.borrow_mut();
+    if data.len() < buffer.len() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    data[..buffer.len()].copy_from_slice(&buffer);
+    Ok(())
+}
+
+// 初始化指令之间的差异，默认值对应普通的 Initialize
+#[derive(Default)]
+struct InitOptions<'a> {
+    // 为 true 时（InitializeAndIncrement）初始化后立即把计数加到1，一次写入账户
+    increment: bool,
+    // 为 None 时授权人取第二个账户并要求其签名；InitializeWithAuthority 直接指定授权人，不需要第二个账户
+    authority: Option<Pubkey>,
+    // 0 表示永不过期
+    expires_at: i64,
+    // 为 Some 时按种子模型校验，base账户排在授权账户之后
+    seed: Option<&'a str>,
+    // 全零表示不关联代币
+    mint: Pubkey,
+    // 0 表示不衰减；非零时以当前slot作为衰减的起点
+    decay_per_slot: u32,
+    // 0 表示不限制自增次数
+    lifetime_limit: u64,
+    // 0 表示不限制自增间隔
+    min_seconds_between: u32,
+}
+
+// 处理初始化指令
+//
+// 计数器账户有三种初始化模型：
+// 1. 密钥对模型：客户端用新生成的密钥对通过 `create_account` 创建账户，
+//    并在同一笔交易中调用 Initialize。此时计数器账户必须对交易签名，
+//    否则任何人都可以抢先初始化别人刚分配好的账户。
+// 2. PDA模型：账户地址由程序派生，只能由程序通过 `invoke_signed` 代签，
+//    不存在外部私钥。PDA计数器只能通过 InitializeBatch 创建（见 process_initialize_batch），
+//    PDA账户在这里会因缺少签名而被拒绝。
+// 3. 种子模型：客户端用 `create_account_with_seed` 以 base + seed + program_id 派生地址创建账户，
+//    同样没有计数器私钥。此时改为要求base账户签名，并校验派生地址与计数器账户一致，
+//    防止他人用自己的base初始化不属于他的地址。
+//
+// 各初始化变体的差异由 InitOptions 描述，见其字段说明。
+// 所有初始化指令都可以在账户列表末尾附加一个注册表账户，新计数器会被追加到注册表中；
+// 不附加时不做登记，与之前的账户列表保持兼容。注册表第一次登记时需要签名，之后不需要。
+fn process_initialize(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    label: [u8; LABEL_LEN],
+    options: InitOptions,
+) -> ProgramResult {
+    let InitOptions { increment, authority, expires_at, seed, mint, decay_per_slot, lifetime_limit, min_seconds_between } =
+        options;
+
+    let account_info_iter = &mut accounts.iter();
+    let counter_account = next_account_info(account_info_iter)?;
+
+    // 确保账户属于当前程序
+    if counter_account.owner != program_id {
+        msg!("Counter account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let authority = match authority {
+        Some(authority) => authority,
+        None => {
+            let authority_account = next_account_info(account_info_iter)?;
+            // 授权账户必须签名，避免把别人的地址设为授权人
+            if !authority_account.is_signer {
+                msg!("Authority account must sign the initialize instruction");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            *authority_account.key
+        }
+    };
+
+    match seed {
+        Some(seed) => {
+            // 种子模型下由base账户签名，并且计数器地址必须由该base和种子派生
+            let base_account = next_account_info(account_info_iter)?;
+            if !base_account.is_signer {
+                msg!("Base account must sign the initialize instruction");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            let expected = Pubkey::create_with_seed(base_account.key, seed, program_id)
+                .map_err(|_| ProgramError::InvalidSeeds)?;
+            if expected != *counter_account.key {
+                msg!("Counter account does not match the address derived from base and seed");
+                return Err(ProgramError::InvalidSeeds);
+            }
+        }
+        None => {
+            // 密钥对模型下，计数器账户本身必须签名
+            if !counter_account.is_signer {
+                msg!("Counter account must sign the initialize instruction");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+    }
+
+    // 可选的注册表账户，必须属于当前程序
+    let registry_account = next_account_info(account_info_iter).ok();
+    if let Some(registry_account) = registry_account {
+        if registry_account.owner != program_id {
+            msg!("Registry account does not have the correct program id");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+    }
+
+    let mut counter_info: Counter = Counter::unpack_lenient_unchecked(&counter_account.data.borrow())?;
+    let original = counter_info.clone();
+    if counter_info.is_initialized {
+        msg!("Counter account already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // 初始化计数器
+    counter_info.is_initialized = true;
+    counter_info.count = 0;
+    counter_info.authority = authority;
+    counter_info.label = label;
+    counter_info.expires_at = expires_at;
+    counter_info.mint = mint;
+    counter_info.decay_per_slot = decay_per_slot;
+    if decay_per_slot != 0 {
+        counter_info.last_slot = Clock::get()?.slot;
+    }
+    counter_info.lifetime_limit = lifetime_limit;
+    counter_info.min_seconds_between = min_seconds_between;
+    let initial_count = counter_info.count;
+    if increment {
+        counter_info.count = math::add(counter_info.count, 1)?;
+    }
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, counter_account)?;
+
+    // 注册表首次使用时自动初始化。全零的账户也可能是别人尚未初始化的计数器，
+    // 因此第一次写入需要注册表账户本身签名
+    if let Some(registry_account) = registry_account {
+        let mut registry = Registry::unpack_unchecked(&registry_account.data.borrow())?;
+        if !registry.is_initialized && !registry_account.is_signer {
+            msg!("Registry account must sign its first registration");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        registry.is_initialized = true;
+        registry.append(*counter_account.key)?;
+        let registered = registry.len;
+        let mut registry_buffer = [0u8; Registry::LEN];
+        Registry::pack(registry, &mut registry_buffer)?;
+        registry_account.data.borrow_mut().copy_from_slice(&registry_buffer);
+        verbose_msg!("Counter registered, registry now holds {} counters", registered);
+    }
+    
+    verbose_msg!("Counter authority: {}, label: {}", authority, decode_label(&label));
+    if expires_at != 0 {
+        verbose_msg!("Counter expires at {}", expires_at);
+    }
+    if mint != Pubkey::default() {
+        verbose_msg!("Counter mint: {}", mint);
+    }
+    if decay_per_slot != 0 {
+        verbose_msg!("Counter decays by {} per slot", decay_per_slot);
+    }
+    if lifetime_limit != 0 {
+        verbose_msg!("Counter lifetime limit: {}", lifetime_limit);
+    }
+    if min_seconds_between != 0 {
+        verbose_msg!("Counter increments at most every {} seconds", min_seconds_between);
+    }
+    msg!("Counter account initialized with count: {}", initial_count);
+    if increment {
+        msg!("Counter incremented to: {}", count);
+    }
+    Ok(())
+}
+
+// 处理预留额外空间的初始化指令：先检查预留大小和账户长度，其余与 Initialize 相同
+fn process_initialize_with_padding(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    extra: u16,
+    label: [u8; LABEL_LEN],
+) -> ProgramResult {
+    if extra > MAX_ACCOUNT_PADDING {
+        msg!("Padding {} exceeds the maximum of {} bytes", extra, MAX_ACCOUNT_PADDING);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let counter_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let required = counter_account_space(extra);
+    if counter_account.data_len() < required {
+        msg!(
+            "Counter account holds {} bytes, {} required for {} bytes of padding",
+            counter_account.data_len(),
+            required,
+            extra
+        );
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    process_initialize(program_id, accounts, label, InitOptions::default())
+}
+
+// 处理按模板初始化的指令：读取模板的配置作为 InitOptions，其余检查与 InitializeWithAuthority 相同
+fn process_initialize_from_template(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let counter_account = next_account_info(account_info_iter)?;
+    let template_account = next_account_info(account_info_iter)?;
+
+    if template_account.owner != program_id {
+        msg!("Template account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if template_account.key == counter_account.key {
+        msg!("Cannot initialize a counter from itself");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if counter_account.data_len() < <Counter>::LEN {
+        msg!("Counter account holds {} bytes, {} required", counter_account.data_len(), <Counter>::LEN);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // unpack 会拒绝未初始化的模板；过期的模板不能再用来创建计数器
+    let template = load_counter(template_account)?;
+    check_not_expired(&template)?;
+    verbose_msg!("Template {} with authority {}", template_account.key, template.authority);
+
+    let options = InitOptions {
+        authority: Some(template.authority),
+        expires_at: template.expires_at,
+        mint: template.mint,
+        decay_per_slot: template.decay_per_slot,
+        lifetime_limit: template.lifetime_limit,
+        min_seconds_between: template.min_seconds_between,
+        ..InitOptions::default()
+    };
+    // process_initialize 在指定授权人时按 [计数器账户, 可选的注册表账户] 读取，去掉模板账户
+    let init_accounts: Vec<AccountInfo> =
+        std::iter::once(counter_account).chain(account_info_iter).cloned().collect();
+    process_initialize(program_id, &init_accounts, template.label, options)
+}
+
+// 处理批量创建PDA计数器的指令：先校验全部地址和付款余额，再逐个通过 invoke_signed 创建并初始化。
+// 创建和初始化都在同一条指令内，任何一步失败时整笔交易回滚
+fn process_initialize_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    count: u8,
+    label: [u8; LABEL_LEN],
+) -> ProgramResult {
+    if count == 0 || count > MAX_BATCH_INITIALIZE {
+        msg!("Batch size {} must be between 1 and {}", count, MAX_BATCH_INITIALIZE);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let account_info_iter = &mut accounts.iter();
+    let payer_account = next_account_info(account_info_iter)?;
+    let base_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let counter_accounts = account_info_iter.as_slice();
+
+    if !payer_account.is_signer || !payer_account.is_writable {
+        msg!("Payer account must be a writable signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    // base 是所有计数器的授权人，必须签名，避免别人用自己的付款账户占用他人的地址
+    if !base_account.is_signer {
+        msg!("Base account must sign the initialize instruction");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if system_program.key != &solana_system_interface::program::ID {
+        msg!("Expected the system program, found {}", system_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 先校验全部地址，避免创建到一半才发现某个账户有误
+    let mut bumps = Vec::with_capacity(count as usize);
+    for (index, counter_account) in (0..count).zip(counter_accounts) {
+        let (expected, bump) = batch_counter_address(program_id, base_account.key, index);
+        if expected != *counter_account.key {
+            msg!("Counter account {} does not match the address derived for index {}", counter_account.key, index);
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if counter_account.lamports() != 0 || !counter_account.data_is_empty() {
+            msg!("Counter account {} already exists", counter_account.key);
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        bumps.push(bump);
+    }
+
+    let rent = Rent::get()?.minimum_balance(<Counter>::LEN);
+    let total = rent.checked_mul(count as u64).ok_or(ProgramError::ArithmeticOverflow)?;
+    if payer_account.lamports() < total {
+        msg!("Payer holds {} lamports, {} required for {} counters", payer_account.lamports(), total, count);
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    for ((index, counter_account), bump) in (0..count).zip(counter_accounts).zip(bumps) {
+        invoke_signed(
+            &solana_system_interface::instruction::create_account(
+                payer_account.key,
+                counter_account.key,
+                rent,
+                <Counter>::LEN as u64,
+                program_id,
+            ),
+            &[payer_account.clone(), counter_account.clone(), system_program.clone()],
+            &[&[BATCH_COUNTER_SEED, base_account.key.as_ref(), &[index], &[bump]]],
+        )?;
+
+        let original: Counter = Counter::unpack_lenient_unchecked(&counter_account.data.borrow())?;
+        let counter_info = Counter::new(0).with_authority(*base_account.key).with_label(label);
+        write_counter(&original, counter_info, counter_account)?;
+        verbose_msg!("Counter {} created at index {}", counter_account.key, index);
+    }
+
+    msg!("Initialized {} counters for base {}", count, base_account.key);
+    Ok(())
+}
+
+// 按当前slot结算衰减；不衰减的计数器不读取 Clock sysvar
+fn settle_now(counter: &mut Counter) -> ProgramResult {
+    if counter.decay_per_slot != 0 {
+        counter.settle(Clock::get()?.slot);
+    }
+    Ok(())
+}
+
+// 读取已初始化的计数器（允许多余的尾部数据）并结算衰减，所有读取计数的处理函数都经过这里
+fn load_counter(account: &AccountInfo) -> Result<Counter, ProgramError> {
+    let mut counter: Counter = Counter::unpack_lenient(&account.data.borrow())?;
+    trace!("unpack", "counter={} count={} total_ops={} in_progress={}", account.key, counter.count, counter.total_ops,
+        counter.in_progress);
+    settle_now(&mut counter)?;
+    trace!("unpack", "settled count={} last_slot={}", counter.count, counter.last_slot);
+    Ok(counter)
+}
+
+// 单个计数器指令的账户：[可写] 计数器账户，之后可选的 [签名] 授权账户。
+// 所有权、过期和授权签名检查集中在这里，新增处理函数时不容易漏掉某项检查
+struct CounterAccounts<'a, 'info> {
+    counter: &'a AccountInfo<'info>,
+    authority: Option<&'a AccountInfo<'info>>,
+}
+
+impl<'a, 'info> CounterAccounts<'a, 'info> {
+    // 取出计数器账户并确认属于当前程序；紧随其后的账户（如果有）作为授权账户
+    fn parse(accounts: &'a [AccountInfo<'info>], program_id: &Pubkey) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let counter = next_account_info(account_info_iter)?;
+
+        // 确保账户属于当前程序
+        if counter.owner != program_id {
+            msg!("Counter account does not have the correct program id");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        trace!("validate", "counter={} owner={} writable={}", counter.key, counter.owner, counter.is_writable);
+
+        Ok(CounterAccounts { counter, authority: account_info_iter.next() })
+    }
+
+    // 读取将要修改的计数器：必须已初始化并且未过期，衰减已结算到当前slot
+    fn load(&self) -> Result<Counter, ProgramError> {
+        let counter = load_counter(self.counter)?;
+        check_not_expired(&counter)?;
+        Ok(counter)
+    }
+
+    // 与 load 相同，并且要求授权账户由计数器记录的authority签名；缺少授权账户时返回 NotEnoughAccountKeys
+    fn load_authorized(&self) -> Result<Counter, ProgramError> {
+        let counter = self.load()?;
+        check_authority(&counter, self.authority.ok_or(ProgramError::NotEnoughAccountKeys)?)?;
+        Ok(counter)
+    }
+}
+
+// 校验授权账户：必须是计数器记录的authority，并且对交易签名
+fn check_authority(counter: &Counter, authority_account: &AccountInfo) -> ProgramResult {
+    trace!("validate", "authority expected={} found={} signer={}", counter.authority, authority_account.key,
+        authority_account.is_signer);
+    if authority_account.key != &counter.authority || !authority_account.is_signer {
+        msg!("Counter authority {} must sign this instruction", counter.authority);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    verbose_msg!("Authority {} signed", counter.authority);
+    Ok(())
+}
+
+// 处理增加计数器值的指令
+fn process_increment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    // 授权账户是可选的，但附带了就必须校验，不能让调用方误以为这次自增经过了授权
+    let mut counter_info = match accounts.authority {
+        Some(_) => accounts.load_authorized()?,
+        None => accounts.load()?,
+    };
+    let original = counter_info.clone();
+    check_increment_allowed(&mut counter_info)?;
+    
+    // 增加计数
+    trace!("arithmetic", "{} + 1", counter_info.count);
+    counter_info.count = math::add(counter_info.count, 1)?;
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, accounts.counter)?;
+    
+    msg!("Counter incremented to: {}", count);
+    Ok(())
+}
+
+// 减少计数，但不能小于0；Decrement、AuthDecrementBy 和 DecrementByAccount 共用
+fn decrement_by(counter: &mut Counter, amount: Count) -> ProgramResult {
+    trace!("arithmetic", "{} - {}", counter.count, amount);
+    counter.count = math::sub(counter.count, amount).map_err(|_| {
+        msg!("Counter cannot be decremented below 0");
+        ProgramError::InvalidArgument
+    })?;
+    Ok(())
+}
+
+// 已过期的计数器拒绝一切修改；单计数器指令通过 CounterAccounts::load 调用，其余修改计数器的处理函数在读取账户后调用
+fn check_not_expired(counter: &Counter) -> ProgramResult {
+    if counter.expires_at != 0 && Clock::get()?.unix_timestamp >= counter.expires_at {
+        msg!("Counter expired at {}", counter.expires_at);
+        return Err(CounterError::Expired.into());
+    }
+    Ok(())
+}
+
+// 检查计数器现在是否允许自增，所有自增指令在修改计数前调用：
+// - 已用完终身额度时返回 LifetimeExhausted。额度按初始化之后的成功修改次数计算（total_ops 包含初始化那一次），
+//   减少计数也会消耗额度
+// - 距上次自增不到 min_seconds_between 秒时返回 TooSoon；允许时把 last_ts 更新为当前时间，随计数一起写回。
+//   不限制间隔的计数器不读取 Clock sysvar
+fn check_increment_allowed(counter: &mut Counter) -> ProgramResult {
+    if counter.lifetime_limit != 0 && counter.total_ops.saturating_sub(1) >= counter.lifetime_limit {
+        msg!("Counter reached its lifetime limit of {} operations", counter.lifetime_limit);
+        return Err(CounterError::LifetimeExhausted.into());
+    }
+    if counter.min_seconds_between != 0 {
+        let now = Clock::get()?.unix_timestamp;
+        let next_allowed = counter.last_ts.saturating_add(counter.min_seconds_between as i64);
+        if now < next_allowed {
+            msg!("Counter can be incremented again in {} seconds", next_allowed - now);
+            return Err(CounterError::TooSoon.into());
+        }
+        counter.last_ts = now;
+    }
+    Ok(())
+}
+
+// 处理减少计数器值的指令
+fn process_decrement(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load()?;
+    let original = counter_info.clone();
+    decrement_by(&mut counter_info, Count::from(1u32))?;
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, accounts.counter)?;
+    
+    msg!("Counter decremented to: {}", count);
+    Ok(())
+}
+
+// 处理修改标签的指令
+fn process_relabel(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    label: [u8; LABEL_LEN],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load_authorized()?;
+    let original = counter_info.clone();
+
+    counter_info.label = label;
+    write_counter(&original, counter_info, accounts.counter)?;
+
+    msg!("Counter relabeled to: {}", decode_label(&label));
+    Ok(())
+}
+
+// 处理比较并设置指令：只有当前值等于expected时才写入new
+fn process_compare_and_set(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    expected: u32,
+    new: u32,
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load_authorized()?;
+    let original = counter_info.clone();
+
+    trace!("arithmetic", "compare expected={} found={} new={}", expected, counter_info.count, new);
+    if counter_info.count != Count::from(expected) {
+        msg!("CompareAndSet failed: expected {}, found {}", expected, counter_info.count);
+        return Err(CounterError::Conflict.into());
+    }
+
+    counter_info.count = Count::from(new);
+    write_counter(&original, counter_info, accounts.counter)?;
+
+    msg!("Counter compare-and-set from {} to: {}", expected, new);
+    Ok(())
+}
+
+// 处理读取授权账户的指令，不修改任何状态
+fn process_get_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+
+    // unpack 会拒绝未初始化的账户
+    let counter_info = load_counter(accounts.counter)?;
+    set_return_data(counter_info.authority.as_ref());
+
+    msg!("Counter authority: {}", counter_info.authority);
+    Ok(())
+}
+
+// 处理读取关联mint的指令，不修改任何状态
+fn process_get_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+
+    // unpack 会拒绝未初始化的账户
+    let counter_info = load_counter(accounts.counter)?;
+    set_return_data(counter_info.mint.as_ref());
+
+    msg!("Counter mint: {}", counter_info.mint);
+    Ok(())
+}
+
+// 处理读取计数器状态的指令，不修改任何状态
+fn process_get_stats(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+
+    // 未初始化的账户同样返回状态，由 is_initialized 字段告诉客户端
+    let mut counter_info: Counter = Counter::unpack_lenient_unchecked(&accounts.counter.data.borrow())?;
+    settle_now(&mut counter_info)?;
+
+    let mut stats = [0u8; 1 + Count::WIDTH + 8 + 32 + 8];
+    stats[0] = counter_info.is_initialized as u8;
+    CounterInt::to_le_bytes(counter_info.count, &mut stats[1..1 + Count::WIDTH]);
+    stats[1 + Count::WIDTH..9 + Count::WIDTH].copy_from_slice(&counter_info.total_ops.to_le_bytes());
+    stats[9 + Count::WIDTH..41 + Count::WIDTH].copy_from_slice(counter_info.mint.as_ref());
+    stats[41 + Count::WIDTH..].copy_from_slice(&counter_info.last_slot.to_le_bytes());
+    set_return_data(&stats);
+
+    msg!(
+        "Counter stats: initialized {}, count {}, total ops {}, mint {}, last slot {}",
+        counter_info.is_initialized,
+        counter_info.count,
+        counter_info.total_ops,
+        counter_info.mint,
+        counter_info.last_slot
+    );
+    Ok(())
+}
+
+// 处理列出注册表中计数器的指令，不修改任何状态
+fn process_list_counters(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let registry_account = next_account_info(account_info_iter)?;
+
+    // 确保账户属于当前程序
+    if registry_account.owner != program_id {
+        msg!("Registry account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 尚未登记过任何计数器的注册表返回空列表
+    let registry = Registry::unpack_unchecked(&registry_account.data.borrow())?;
+    let counters = registry.counters()?;
+    let mut data = Vec::with_capacity(counters.len() * 32);
+    for counter in counters {
+        data.extend_from_slice(counter.as_ref());
+    }
+    set_return_data(&data);
+
+    msg!("Registry holds {} counters", counters.len());
+    Ok(())
+}
+
+// 处理检查账户格式的指令，不修改任何状态；格式问题通过返回的状态报告，而不是让指令失败
+fn process_validate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+
+    let status = validate_counter_data(&accounts.counter.data.borrow());
+    set_return_data(&[status as u8]);
+
+    msg!("Counter account status: {:?}", status);
+    Ok(())
+}
+
+// 处理以十进制字符串返回计数的指令，不修改任何状态
+fn process_get_count_string(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+
+    let counter_info = load_counter(accounts.counter)?;
+    let count = counter_info.count.to_string();
+    debug_assert!(count.len() <= COUNT_STRING_MAX_LEN);
+    set_return_data(count.as_bytes());
+
+    msg!("Counter count string: {}", count);
+    Ok(())
+}
+
+// 处理返回计数取模结果的指令，不修改任何状态
+fn process_get_count_mod(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    divisor: u32,
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+
+    let counter_info = load_counter(accounts.counter)?;
+    let remainder = math::rem(counter_info.count, divisor).inspect_err(|_| {
+        msg!("Divisor must not be zero");
+    })?;
+    set_return_data(&remainder.to_le_bytes());
+
+    msg!("Counter count {} mod {}: {}", counter_info.count, divisor, remainder);
+    Ok(())
+}
+
+// 处理返回程序配置的指令，不读取任何账户
+fn process_get_config() -> ProgramResult {
+    let config = ProgramConfig::current();
+    set_return_data(&encode_program_config(&config)?);
+
+    msg!(
+        "Counter config: account len {}, layout version {}, program version {}",
+        config.counter_len,
+        config.layout_version,
+        config.version
+    );
+    Ok(())
+}
+
+// 处理自增到目标值的指令，每次调用的自增次数有上限
+fn process_increment_until(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    target: u32,
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load()?;
+    let original = counter_info.clone();
+    let target = Count::from(target);
+
+    let count = math::add_capped(counter_info.count, Count::from(MAX_INCREMENT_ITERATIONS), target);
+    // 差值不超过 MAX_INCREMENT_ITERATIONS，一定能放进u32
+    let applied = count_to_i64(math::sub(count, counter_info.count)?)? as u32;
+    counter_info.count = count;
+    set_return_data(&applied.to_le_bytes());
+
+    // 已经达到或超过目标时不做任何修改
+    if applied == 0 {
+        msg!("Counter already at or above target {}", target);
+        return Ok(());
+    }
+    check_increment_allowed(&mut counter_info)?;
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, accounts.counter)?;
+
+    verbose_msg!("Applied {} increments toward target {}", applied, target);
+    msg!("Counter incremented to: {}", count);
+    Ok(())
 }
 
-// 定义计数器状态结构
-#[derive(Debug, Default)]
-pub struct Counter {
-    pub is_initialized: bool,
-    pub count: u32,
+// 处理批量减1的指令，计数已经为0的账户保持不变
+fn process_decrement_all(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    skip_invalid: bool,
+) -> ProgramResult {
+    let mut decremented: u32 = 0;
+    let mut skipped: u32 = 0;
+
+    for counter_account in accounts {
+        // 确保账户属于当前程序、是已初始化的计数器、没有过期并且不在修改中；
+        // 任何一项不满足都按 skip_invalid 跳过或者让整条指令失败
+        let counter_info = if counter_account.owner != program_id {
+            Err(ProgramError::IncorrectProgramId)
+        } else {
+            load_counter(counter_account).and_then(|counter_info| {
+                check_not_expired(&counter_info)?;
+                if counter_info.in_progress {
+                    return Err(CounterError::Reentrancy.into());
+                }
+                Ok(counter_info)
+            })
+        };
+        let mut counter_info: Counter = match counter_info {
+            Ok(counter_info) => counter_info,
+            Err(err) if skip_invalid => {
+                msg!("Skipping invalid counter account {}: {}", counter_account.key, err);
+                skipped += 1;
+                continue;
+            }
+            Err(err) => {
+                msg!("Invalid counter account {}: {}", counter_account.key, err);
+                return Err(err);
+            }
+        };
+
+        if counter_info.count == 0 {
+            continue;
+        }
+        let original = counter_info.clone();
+
+        counter_info.count = math::sub(counter_info.count, 1)?;
+        write_counter(&original, counter_info, counter_account)?;
+        decremented += 1;
+    }
+
+    msg!("Decremented {} counters, skipped {} invalid accounts", decremented, skipped);
+    Ok(())
 }
 
-// 实现Pack trait以便序列化和反序列化
-impl Sealed for Counter {}
+// 处理带幂等键的自增指令，重复的键不会再次自增
+fn process_increment_idempotent(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    key: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let counter_account = next_account_info(account_info_iter)?;
+    let cache_account = next_account_info(account_info_iter)?;
 
-impl IsInitialized for Counter {
-    fn is_initialized(&self) -> bool {
-        self.is_initialized
+    // 确保两个账户都属于当前程序
+    if counter_account.owner != program_id || cache_account.owner != program_id {
+        msg!("Counter or cache account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut counter_info = load_counter(counter_account)?;
+    let original = counter_info.clone();
+    check_not_expired(&counter_info)?;
+    let mut cache = IdempotencyCache::unpack_unchecked(&cache_account.data.borrow())?;
+
+    // 缓存首次使用时绑定到当前计数器，之后只能用于这个计数器
+    if !cache.is_initialized {
+        cache.is_initialized = true;
+        cache.counter = *counter_account.key;
+    } else if cache.counter != *counter_account.key {
+        msg!("Idempotency cache belongs to counter {}", cache.counter);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if cache.contains(key)? {
+        msg!("Idempotency key {} already seen, duplicate suppressed", key);
+        return Ok(());
     }
+
+    check_increment_allowed(&mut counter_info)?;
+    counter_info.count = math::add(counter_info.count, 1)?;
+    cache.record(key)?;
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, counter_account)?;
+    // 与 write_counter 一样先打包到本地缓冲区，成功后再写入账户
+    let mut cache_buffer = [0u8; IdempotencyCache::LEN];
+    IdempotencyCache::pack(cache, &mut cache_buffer)?;
+    cache_account.data.borrow_mut().copy_from_slice(&cache_buffer);
+
+    msg!("Counter incremented to: {}", count);
+    Ok(())
+}
+
+// 处理支付指令：余额不足时整体失败，不做部分扣减
+fn process_spend(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u32,
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load()?;
+    let original = counter_info.clone();
+
+    verbose_msg!("Spending {} from balance {}", amount, counter_info.count);
+    counter_info.count = math::sub(counter_info.count, Count::from(amount))
+        .map_err(|_| CounterError::InsufficientBalance)?;
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, accounts.counter)?;
+
+    msg!("Counter spent to: {}", count);
+    Ok(())
 }
 
-impl Pack for Counter {
-    const LEN: usize = 5; // 1 byte for is_initialized + 4 bytes for count (u32)
+// 处理转移授权的指令
+fn process_set_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Pubkey,
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load_authorized()?;
+    let original = counter_info.clone();
 
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        if src.len() != Self::LEN {
-            return Err(ProgramError::InvalidAccountData);
+    counter_info.authority = new_authority;
+    write_counter(&original, counter_info, accounts.counter)?;
+
+    msg!("Counter authority set to {}", new_authority);
+    Ok(())
+}
+
+// 处理需要授权的批量减少指令
+fn process_auth_decrement_by(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u32,
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load_authorized()?;
+    let original = counter_info.clone();
+    decrement_by(&mut counter_info, Count::from(amount))?;
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, accounts.counter)?;
+
+    msg!("Counter decremented by {} to: {}", amount, count);
+    Ok(())
+}
+
+// 处理需要授权的设值指令
+fn process_auth_set_value(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    value: u32,
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load_authorized()?;
+    let original = counter_info.clone();
+
+    counter_info.count = Count::from(value);
+    write_counter(&original, counter_info, accounts.counter)?;
+
+    msg!("Counter set from {} to: {}", original.count, value);
+    Ok(())
+}
+
+// 处理带结果报告的减少指令，不够减时报告可用的计数而不是失败
+fn process_decrement_by_report(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u32,
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load()?;
+    let original = counter_info.clone();
+
+    let mut report = [0u8; 1 + Count::WIDTH];
+    match math::sub(counter_info.count, Count::from(amount)) {
+        Ok(count) => {
+            counter_info.count = count;
+            report[0] = 1;
+            CounterInt::to_le_bytes(count, &mut report[1..]);
+            write_counter(&original, counter_info, accounts.counter)?;
+            msg!("Counter decremented by {} to: {}", amount, count);
         }
+        Err(_) => {
+            CounterInt::to_le_bytes(counter_info.count, &mut report[1..]);
+            msg!("Cannot decrement by {}, only {} available", amount, counter_info.count);
+        }
+    }
+    set_return_data(&report);
+    Ok(())
+}
 
-        let is_initialized = src[0] != 0;
-        let count = u32::from_le_bytes([src[1], src[2], src[3], src[4]]);
+// 处理按顺序批量自增的指令，全部成功后才写入账户
+fn process_increment_sequence(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amounts: &[u32],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load()?;
+    let original = counter_info.clone();
 
-        Ok(Counter {
-            is_initialized,
-            count,
-        })
+    // 空序列不修改任何状态，也不占用冷却时间或 total_ops
+    if amounts.is_empty() {
+        msg!("IncrementSequence has no amounts, counter unchanged");
+        return Ok(());
     }
 
-    fn pack_into_slice(&self, dst: &mut [u8]) {
-        dst[0] = self.is_initialized as u8;
-        let count_bytes = self.count.to_le_bytes();
-        dst[1..5].copy_from_slice(&count_bytes);
+    check_increment_allowed(&mut counter_info)?;
+    for (step, &amount) in amounts.iter().enumerate() {
+        counter_info.count = math::add(counter_info.count, Count::from(amount)).inspect_err(|_| {
+            msg!("IncrementSequence overflowed at step {} (amount {})", step, amount);
+        })?;
     }
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, accounts.counter)?;
+
+    verbose_msg!("Applied {} increments", amounts.len());
+    msg!("Counter incremented to: {}", count);
+    Ok(())
 }
 
-// 解析指令数据
-fn unpack_instruction_data(instruction_data: &[u8]) -> Result<CounterInstruction, ProgramError> {
-    if instruction_data.is_empty() {
-        return Err(ProgramError::InvalidInstructionData);
+// 处理按另一个计数器的计数减少的指令
+fn process_decrement_by_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let counter_account = next_account_info(account_info_iter)?;
+    let source_account = next_account_info(account_info_iter)?;
+
+    // 确保两个账户都属于当前程序
+    if counter_account.owner != program_id || source_account.owner != program_id {
+        msg!("Counter or source account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
     }
+    trace!("validate", "counter={} source={}", counter_account.key, source_account.key);
 
-    Ok(match instruction_data[0] {
-        0 => CounterInstruction::Initialize,
-        1 => CounterInstruction::Increment,
-        2 => CounterInstruction::Decrement,
-        _ => return Err(ProgramError::InvalidInstructionData),
-    })
+    // 两个账户都按计数器解析，长度不足或未初始化时 unpack 会失败
+    let amount = load_counter(source_account)?.count;
+    let mut counter_info = load_counter(counter_account)?;
+    let original = counter_info.clone();
+    check_not_expired(&counter_info)?;
+    decrement_by(&mut counter_info, amount)?;
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, counter_account)?;
+
+    msg!("Counter decremented by {} from {} to: {}", amount, source_account.key, count);
+    Ok(())
 }
 
-// 处理初始化指令
-fn process_initialize(
+// 处理交换两个计数器计数的指令
+fn process_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let first_account = next_account_info(account_info_iter)?;
+    let second_account = next_account_info(account_info_iter)?;
+
+    // 确保两个账户都属于当前程序
+    if first_account.owner != program_id || second_account.owner != program_id {
+        msg!("Counter accounts do not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if first_account.key == second_account.key {
+        msg!("Cannot swap a counter with itself");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // 先读取并检查两个计数器，全部通过后再写入，任何一个失败时两个账户都不会被修改
+    let mut first = load_counter(first_account)?;
+    let first_original = first.clone();
+    check_not_expired(&first)?;
+    let mut second = load_counter(second_account)?;
+    let second_original = second.clone();
+    check_not_expired(&second)?;
+
+    trace!("arithmetic", "swap {}={} {}={}", first_account.key, first.count, second_account.key, second.count);
+    std::mem::swap(&mut first.count, &mut second.count);
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let (first_count, second_count) = (first.count, second.count);
+    write_counter(&first_original, first, first_account)?;
+    write_counter(&second_original, second, second_account)?;
+
+    // 不使用 "Counter ... to: N" 格式：同一笔交易涉及两个计数器，history 无法区分
+    msg!("Swapped counts: {} now {}, {} now {}", first_account.key, first_count, second_account.key, second_count);
+    Ok(())
+}
+
+// 处理减少计数并付款的指令
+fn process_decrement_and_pay(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
+    amount: u32,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let counter_account = next_account_info(account_info_iter)?;
+    let payer_account = next_account_info(account_info_iter)?;
+    let recipient_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
 
-    // 确保账户没有被初始化
+    // 确保账户属于当前程序
     if counter_account.owner != program_id {
         msg!("Counter account does not have the correct program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let mut counter_info = Counter::unpack_unchecked(&counter_account.data.borrow())?;
-    if counter_info.is_initialized {
-        msg!("Counter account already initialized");
-        return Err(ProgramError::AccountAlreadyInitialized);
+    // 转账通过CPI调用系统程序完成，必须传入真正的系统程序
+    if system_program.key != &solana_system_interface::program::ID {
+        msg!("Expected the system program, found {}", system_program.key);
+        return Err(ProgramError::IncorrectProgramId);
     }
 
-    // 初始化计数器
-    counter_info.is_initialized = true;
-    counter_info.count = 0;
+    let mut counter_info = load_counter(counter_account)?;
+    let original = counter_info.clone();
+    check_not_expired(&counter_info)?;
+    decrement_by(&mut counter_info, Count::from(amount))?;
+
+    if payer_account.lamports() < amount as u64 {
+        msg!("Payer holds {} lamports, needs {}", payer_account.lamports(), amount);
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    // CPI期间把计数器标记为修改中，调用链中重入本程序修改这个计数器的指令会返回 Reentrancy；
+    // 结束后的 write_counter 写入的状态不带标志
+    if original.in_progress {
+        msg!("Counter {} is already being modified", counter_account.key);
+        return Err(CounterError::Reentrancy.into());
+    }
+    store_counter(Counter { in_progress: true, ..original.clone() }, counter_account)?;
+
+    // 付款账户的签名由系统程序校验
+    invoke(
+        &solana_system_interface::instruction::transfer(payer_account.key, recipient_account.key, amount as u64),
+        &[payer_account.clone(), recipient_account.clone(), system_program.clone()],
+    )?;
 
     // 保存数据前先记录值，避免移动后使用错误
     let count = counter_info.count;
-    Counter::pack(counter_info, &mut counter_account.data.borrow_mut())?;
-    
-    msg!("Counter account initialized with count: {}", count);
+    if counter_info == original {
+        // 计数没有变化时 write_counter 不会写入，需要写回读取时的状态来清除标志
+        store_counter(original, counter_account)?;
+    } else {
+        write_counter(&original, counter_info, counter_account)?;
+    }
+
+    msg!("Paid {} lamports to {}", amount, recipient_account.key);
+    msg!("Counter decremented by {} to: {}", amount, count);
     Ok(())
 }
 
-// 处理增加计数器值的指令
-fn process_increment(
+// 处理关闭计数器的指令。
+// 最初5字节布局的旧账户没有记录授权账户（unpack_legacy 读出的是全零地址，没有人能为它签名），
+// 因此 Close 对它们总是返回 MissingRequiredSignature；旧账户必须先迁移到当前布局并设置授权账户才能关闭
+fn process_close(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let counter_account = next_account_info(account_info_iter)?;
+    let authority_account = next_account_info(account_info_iter)?;
+    let destination_account = next_account_info(account_info_iter)?;
 
     // 确保账户属于当前程序
     if counter_account.owner != program_id {
@@ -120,55 +2630,199 @@ fn process_increment(
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let mut counter_info = Counter::unpack(&counter_account.data.borrow())?;
-    
-    // 增加计数
-    counter_info.count = counter_info.count.checked_add(1)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
+    let counter_info: Counter = Counter::unpack_lenient(&counter_account.data.borrow())?;
+    check_authority(&counter_info, authority_account)?;
+
+    // 接收方必须可写，并且不能是计数器自身，否则lamports会留在待关闭的账户里
+    if !destination_account.is_writable || destination_account.key == counter_account.key {
+        msg!("Close destination must be a writable account other than the counter");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let amount = counter_account.lamports();
+    **destination_account.lamports.borrow_mut() = math::add(destination_account.lamports(), amount)?;
+    **counter_account.lamports.borrow_mut() = 0;
+
+    // 清零账户数据（包括 is_initialized），即使在同一笔交易中再次读取也不会被当作有效的计数器
+    counter_account.data.borrow_mut().fill(0);
+
+    msg!("Counter closed, moved {} lamports to {}", amount, destination_account.key);
+    Ok(())
+}
+
+// 处理低于阈值时才自增的指令
+fn process_increment_if_below(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    threshold: u32,
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load()?;
+    let original = counter_info.clone();
+
+    if counter_info.count >= Count::from(threshold) {
+        msg!("Counter threshold reached: {} >= {}", counter_info.count, threshold);
+        return Ok(());
+    }
+
+    check_increment_allowed(&mut counter_info)?;
+    counter_info.count = math::add(counter_info.count, 1)?;
 
     // 保存数据前先记录值，避免移动后使用错误
     let count = counter_info.count;
-    Counter::pack(counter_info, &mut counter_account.data.borrow_mut())?;
-    
+    write_counter(&original, counter_info, accounts.counter)?;
+
     msg!("Counter incremented to: {}", count);
     Ok(())
 }
 
-// 处理减少计数器值的指令
-fn process_decrement(
+// 处理按其他账户中的标志字节决定是否自增的指令
+fn process_increment_if_flag(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
+    offset: u32,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let counter_account = next_account_info(account_info_iter)?;
+    let flag_account = next_account_info(account_info_iter)?;
 
-    // 确保账户属于当前程序
+    // 只有计数器账户需要属于当前程序，标志账户的数据由链上状态决定，客户端无法伪造
     if counter_account.owner != program_id {
         msg!("Counter account does not have the correct program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let mut counter_info = Counter::unpack(&counter_account.data.borrow())?;
-    
-    // 减少计数，但不能小于0
-    if counter_info.count == 0 {
-        msg!("Counter cannot be decremented below 0");
-        return Err(ProgramError::InvalidArgument);
+    let mut counter_info = load_counter(counter_account)?;
+    let original = counter_info.clone();
+    check_not_expired(&counter_info)?;
+
+    let flag = match flag_account.data.borrow().get(offset as usize) {
+        Some(&flag) => flag,
+        None => {
+            msg!("Flag offset {} is out of bounds for account {}", offset, flag_account.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+    };
+    if flag == 0 {
+        msg!("Flag at offset {} of {} is not set", offset, flag_account.key);
+        return Ok(());
     }
-    
-    counter_info.count = counter_info.count.checked_sub(1)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    check_increment_allowed(&mut counter_info)?;
+    counter_info.count = math::add(counter_info.count, 1)?;
 
     // 保存数据前先记录值，避免移动后使用错误
     let count = counter_info.count;
-    Counter::pack(counter_info, &mut counter_account.data.borrow_mut())?;
-    
-    msg!("Counter decremented to: {}", count);
+    write_counter(&original, counter_info, counter_account)?;
+
+    msg!("Counter incremented to: {}", count);
+    Ok(())
+}
+
+// 处理按当前纪元号自增的指令
+fn process_increment_by_epoch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load()?;
+    let original = counter_info.clone();
+
+    check_increment_allowed(&mut counter_info)?;
+    let epoch = Clock::get()?.epoch;
+    // 计数至少是u32，纪元号超过 u32::MAX 时按溢出处理
+    let amount = u32::try_from(epoch).map_err(|_| ProgramError::ArithmeticOverflow)?;
+    counter_info.count = math::add(counter_info.count, Count::from(amount))?;
+
+    // 保存数据前先记录值，避免移动后使用错误
+    let count = counter_info.count;
+    write_counter(&original, counter_info, accounts.counter)?;
+
+    msg!("Using epoch {}", epoch);
+    msg!("Counter incremented to: {}", count);
     Ok(())
 }
 
-// 声明程序的入口点
-entrypoint!(process_instruction);
+// 处理读取并清零的指令，返回的是清零前的计数
+fn process_get_and_reset(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let accounts = CounterAccounts::parse(accounts, program_id)?;
+    let mut counter_info = accounts.load_authorized()?;
+    let original = counter_info.clone();
+
+    let mut previous = [0u8; Count::WIDTH];
+    CounterInt::to_le_bytes(counter_info.count, &mut previous);
+
+    counter_info.count = Count::default();
+    write_counter(&original, counter_info, accounts.counter)?;
+    set_return_data(&previous);
+
+    msg!("Counter drained {}, reset to: 0", original.count);
+    Ok(())
+}
+
+// 程序自身的构建版本，在每条指令开始时写入日志，便于确认线上运行的是哪个构建；
+// 与账户数据布局版本无关。发布有意义的改动时提升 Cargo.toml 中的版本号
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// GetConfig 返回的程序配置，均为编译期常量
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramConfig {
+    // 计数器账户的数据长度 Counter::LEN，随计数宽度变化
+    pub counter_len: u16,
+    // 写入账户的版本字节 ACCOUNT_VERSION；大端构建带有 BIG_ENDIAN_FLAG
+    pub layout_version: u8,
+    // 程序构建版本 VERSION
+    pub version: String,
+}
+
+impl ProgramConfig {
+    // 本次编译的配置
+    pub fn current() -> Self {
+        ProgramConfig {
+            counter_len: Counter::<Count>::LEN as u16,
+            layout_version: ACCOUNT_VERSION,
+            version: VERSION.to_string(),
+        }
+    }
+}
+
+// 编码 GetConfig 的返回数据：
+//   [0..2]      counter_len（小端u16）
+//   [2]         layout_version
+//   [3]         版本字符串的字节数n
+//   [4..4 + n]  版本字符串（UTF-8）
+// 版本字符串超过255字节时返回 InvalidArgument
+pub fn encode_program_config(config: &ProgramConfig) -> Result<Vec<u8>, ProgramError> {
+    let version_len = u8::try_from(config.version.len()).map_err(|_| ProgramError::InvalidArgument)?;
+    let mut data = Vec::with_capacity(4 + config.version.len());
+    data.extend_from_slice(&config.counter_len.to_le_bytes());
+    data.push(config.layout_version);
+    data.push(version_len);
+    data.extend_from_slice(config.version.as_bytes());
+    Ok(data)
+}
+
+// 解码 GetConfig 的返回数据；长度与前缀不符或版本字符串不是UTF-8时返回 InvalidArgument。
+// 运行时会截掉 return data 末尾的零字节，非空的版本字符串不以零字节结尾，因此不受影响
+pub fn decode_program_config(data: &[u8]) -> Result<ProgramConfig, ProgramError> {
+    let (header, version) = data.split_at_checked(4).ok_or(ProgramError::InvalidArgument)?;
+    if version.len() != header[3] as usize {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let version = std::str::from_utf8(version).map_err(|_| ProgramError::InvalidArgument)?;
+    Ok(ProgramConfig {
+        counter_len: u16::from_le_bytes([header[0], header[1]]),
+        layout_version: header[2],
+        version: version.to_string(),
+    })
+}
+
+// 声明程序的入口点；作为库被其他程序或wasm包引用时用 no-entrypoint 去掉
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
 // 程序入口处理函数
 pub fn process_instruction(
@@ -176,15 +2830,114 @@ pub fn process_instruction(
     accounts: &[AccountInfo],   // 账户列表
     instruction_data: &[u8],    // 指令数据
 ) -> ProgramResult {
-    msg!("Counter程序启动");
+    verbose_msg!("Counter程序启动");
+    msg!("Counter program version {}", VERSION);
     
     // 解析指令
     let instruction = unpack_instruction_data(instruction_data)?;
+    verbose_msg!("Instruction {} with {} accounts", instruction.name(), accounts.len());
+    for account in accounts {
+        verbose_msg!("Account {}: signer {}, writable {}", account.key, account.is_signer, account.is_writable);
+    }
+
+    // 账户数量不对时直接失败，而不是在处理函数中途报出难以理解的错误
+    let (min_accounts, max_accounts) = instruction.account_count();
+    if accounts.len() < min_accounts {
+        msg!("{} requires at least {} accounts, got {}", instruction.name(), min_accounts, accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if accounts.len() > max_accounts {
+        msg!("{} accepts at most {} accounts, got {}", instruction.name(), max_accounts, accounts.len());
+        return Err(CounterError::TooManyAccounts.into());
+    }
+
+    // 链上程序以 panic=abort 编译，catch_unwind 无法拦截panic，因此处理函数内不直接索引
+    // 账户或指令数据：所有切片访问都先检查长度（或使用 get），越界时返回 ProgramError
     
     // 根据指令类型调用相应的处理函数
     match instruction {
-        CounterInstruction::Initialize => process_initialize(program_id, accounts),
+        CounterInstruction::Initialize { label } => {
+            process_initialize(program_id, accounts, label, InitOptions::default())
+        }
         CounterInstruction::Increment => process_increment(program_id, accounts),
         CounterInstruction::Decrement => process_decrement(program_id, accounts),
+        CounterInstruction::Relabel { label } => process_relabel(program_id, accounts, label),
+        CounterInstruction::CompareAndSet { expected, new } => {
+            process_compare_and_set(program_id, accounts, expected, new)
+        }
+        CounterInstruction::GetAuthority => process_get_authority(program_id, accounts),
+        CounterInstruction::GetStats => process_get_stats(program_id, accounts),
+        CounterInstruction::GetCountString => process_get_count_string(program_id, accounts),
+        CounterInstruction::GetCountMod { divisor } => process_get_count_mod(program_id, accounts, divisor),
+        CounterInstruction::GetConfig => process_get_config(),
+        CounterInstruction::ListCounters => process_list_counters(program_id, accounts),
+        CounterInstruction::Validate => process_validate(program_id, accounts),
+        CounterInstruction::IncrementByEpoch => process_increment_by_epoch(program_id, accounts),
+        CounterInstruction::GetAndReset => process_get_and_reset(program_id, accounts),
+        CounterInstruction::GetMint => process_get_mint(program_id, accounts),
+        CounterInstruction::IncrementIfFlag { offset } => process_increment_if_flag(program_id, accounts, offset),
+        CounterInstruction::InitializeWithPadding { extra, label } => {
+            process_initialize_with_padding(program_id, accounts, extra, label)
+        }
+        CounterInstruction::InitializeWithDecay { decay_per_slot, label } => {
+            process_initialize(program_id, accounts, label, InitOptions { decay_per_slot, ..InitOptions::default() })
+        }
+        CounterInstruction::InitializeWithLifetimeLimit { lifetime_limit, label } => {
+            process_initialize(program_id, accounts, label, InitOptions { lifetime_limit, ..InitOptions::default() })
+        }
+        CounterInstruction::InitializeWithCooldown { min_seconds_between, label } => {
+            let options = InitOptions { min_seconds_between, ..InitOptions::default() };
+            process_initialize(program_id, accounts, label, options)
+        }
+        CounterInstruction::InitializeFromTemplate => process_initialize_from_template(program_id, accounts),
+        CounterInstruction::InitializeBatch { count, label } => {
+            process_initialize_batch(program_id, accounts, count, label)
+        }
+        CounterInstruction::InitializeWithMint { mint, label } => {
+            process_initialize(program_id, accounts, label, InitOptions { mint, ..InitOptions::default() })
+        }
+        CounterInstruction::InitializeWithAuthority { authority } => {
+            let options = InitOptions { authority: Some(authority), ..InitOptions::default() };
+            process_initialize(program_id, accounts, [0u8; LABEL_LEN], options)
+        }
+        CounterInstruction::InitializeWithExpiry { expires_at, label } => {
+            process_initialize(program_id, accounts, label, InitOptions { expires_at, ..InitOptions::default() })
+        }
+        CounterInstruction::AuthDecrementBy { amount } => {
+            process_auth_decrement_by(program_id, accounts, amount)
+        }
+        CounterInstruction::AuthSetValue { value } => process_auth_set_value(program_id, accounts, value),
+        CounterInstruction::DecrementByReport { amount } => {
+            process_decrement_by_report(program_id, accounts, amount)
+        }
+        CounterInstruction::InitializeAndIncrement { label } => {
+            process_initialize(program_id, accounts, label, InitOptions { increment: true, ..InitOptions::default() })
+        }
+        CounterInstruction::InitializeWithSeed { seed, label } => {
+            process_initialize(program_id, accounts, label, InitOptions { seed: Some(&seed), ..InitOptions::default() })
+        }
+        CounterInstruction::IncrementSequence { amounts } => {
+            process_increment_sequence(program_id, accounts, &amounts)
+        }
+        CounterInstruction::DecrementByAccount => process_decrement_by_account(program_id, accounts),
+        CounterInstruction::Swap => process_swap(program_id, accounts),
+        CounterInstruction::DecrementAndPay { amount } => process_decrement_and_pay(program_id, accounts, amount),
+        CounterInstruction::Close => process_close(program_id, accounts),
+        CounterInstruction::IncrementIfBelow { threshold } => {
+            process_increment_if_below(program_id, accounts, threshold)
+        }
+        CounterInstruction::IncrementUntil { target } => {
+            process_increment_until(program_id, accounts, target)
+        }
+        CounterInstruction::DecrementAll { skip_invalid } => {
+            process_decrement_all(program_id, accounts, skip_invalid)
+        }
+        CounterInstruction::IncrementIdempotent { key } => {
+            process_increment_idempotent(program_id, accounts, key)
+        }
+        CounterInstruction::Spend { amount } => process_spend(program_id, accounts, amount),
+        CounterInstruction::SetAuthority { new_authority } => {
+            process_set_authority(program_id, accounts, new_authority)
+        }
     }
 }
\ No newline at end of file
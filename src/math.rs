@@ -0,0 +1,36 @@
+// 计数运算：处理函数对计数和 total_ops 的加减以及取模都经过这里，溢出、不足和上下限的行为集中在一处，
+// 便于审查，边界情况见 tests/math.rs。函数对任意 CounterInt 泛型，u32 和 u128 两种计数宽度行为一致
+use {crate::CounterInt, solana_program::program_error::ProgramError};
+
+// 加上delta，结果超出类型范围时返回 ArithmeticOverflow
+pub fn add<T: CounterInt>(count: T, delta: T) -> Result<T, ProgramError> {
+    count.checked_add(delta).ok_or(ProgramError::ArithmeticOverflow)
+}
+
+// 减去delta，结果小于0时返回 ArithmeticOverflow；需要其他错误的调用方自行 map_err
+pub fn sub<T: CounterInt>(count: T, delta: T) -> Result<T, ProgramError> {
+    count.checked_sub(delta).ok_or(ProgramError::ArithmeticOverflow)
+}
+
+// 加上delta，但结果不超过max；加法溢出时同样得到max。count 已经不小于max时原样返回，不会因此减少计数。
+// 有意不返回 Result：结果总在 count 与 max(count, max) 之间，一定能用 T 表示，没有可报告的错误；
+// 调用方（IncrementUntil）把到达上限当作正常结束而不是失败。用 T 而不是 u32，以同时支持 u128-count
+pub fn add_capped<T: CounterInt>(count: T, delta: T, max: T) -> T {
+    if count >= max {
+        return count;
+    }
+    match count.checked_add(delta) {
+        Some(sum) if sum < max => sum,
+        _ => max,
+    }
+}
+
+// 减去delta，最低为0；delta 超出类型范围时同样得到0
+pub fn sub_floor<T: CounterInt>(count: T, delta: u128) -> T {
+    count.saturating_sub_u128(delta)
+}
+
+// 除以divisor的余数，divisor 为 0 时返回 InvalidArgument
+pub fn rem<T: CounterInt>(count: T, divisor: u32) -> Result<u32, ProgramError> {
+    count.checked_rem_u32(divisor).ok_or(ProgramError::InvalidArgument)
+}
@@ -0,0 +1,62 @@
+// 浏览器端使用的解码函数：wasm-bindgen 导出 decode_counter，前端直接把 getAccountInfo
+// 拿到的账户数据交给它，得到与链上 Counter 字段一一对应的JS对象。
+//
+//     import init, { decode_counter } from "./pkg/rust_solana.js";
+//
+//     await init();
+//     const info = await connection.getAccountInfo(counterPubkey);
+//     const counter = decode_counter(info.data);
+//     console.log(counter.label, BigInt(counter.count));
+use {
+    crate::{decode_label, Counter},
+    serde::Serialize,
+    wasm_bindgen::prelude::*,
+};
+
+// 导出给JS的计数器视图。公钥用base58字符串；count 和 u64 字段用十进制字符串，
+// 因为JS的number超过2^53就会丢失精度，前端需要时用 BigInt() 转换
+#[derive(Serialize)]
+struct CounterView {
+    is_initialized: bool,
+    count: String,
+    authority: String,
+    label: String,
+    expires_at: i64,
+    total_ops: String,
+    mint: String,
+    decay_per_slot: u32,
+    last_slot: String,
+    lifetime_limit: String,
+    min_seconds_between: u32,
+    last_ts: i64,
+    in_progress: bool,
+}
+
+impl From<Counter> for CounterView {
+    fn from(counter: Counter) -> Self {
+        CounterView {
+            is_initialized: counter.is_initialized,
+            count: counter.count.to_string(),
+            authority: counter.authority.to_string(),
+            label: decode_label(&counter.label),
+            expires_at: counter.expires_at,
+            total_ops: counter.total_ops.to_string(),
+            mint: counter.mint.to_string(),
+            decay_per_slot: counter.decay_per_slot,
+            last_slot: counter.last_slot.to_string(),
+            lifetime_limit: counter.lifetime_limit.to_string(),
+            min_seconds_between: counter.min_seconds_between,
+            last_ts: counter.last_ts,
+            in_progress: counter.in_progress,
+        }
+    }
+}
+
+// 解码计数器账户数据。与 load_counter 一样允许尾部有预留空间；数据无效时抛出带错误描述的JS异常。
+// 返回的是账户里存储的原始状态，衰减要到下一笔交易才结算，前端需要时自行按 last_slot 计算
+#[wasm_bindgen]
+pub fn decode_counter(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let counter = Counter::unpack_lenient(bytes).map_err(|err| JsValue::from_str(&err.to_string()))?;
+    let json = serde_json::to_string(&CounterView::from(counter)).map_err(|err| JsValue::from_str(&err.to_string()))?;
+    js_sys::JSON::parse(&json)
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use {
+    common::{create_counter, get_counter, send, start},
+    rust_solana::{
+        authority_instruction, decode_label, encode_label, increment_instruction_with_authority, Count,
+        CounterInstruction,
+    },
+    solana_program::{instruction::InstructionError, pubkey::Pubkey},
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+};
+
+fn assert_missing_signature(err: solana_program_test::BanksClientError) {
+    assert_eq!(
+        err.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn relabel_requires_the_stored_authority() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let relabel = CounterInstruction::Relabel { label: encode_label("renamed").unwrap() };
+
+    // 由错误的密钥签名
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &intruder.pubkey(), &relabel);
+    let err = send(&mut context, &[ix], &[&intruder]).await.unwrap_err();
+    assert_missing_signature(err);
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert_eq!(decode_label(&state.label), "test counter");
+
+    // 由正确的授权账户签名
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &relabel);
+    send(&mut context, &[ix], &[&authority]).await.unwrap();
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert_eq!(decode_label(&state.label), "renamed");
+}
+
+#[tokio::test]
+async fn compare_and_set_requires_the_stored_authority() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let cas = CounterInstruction::CompareAndSet { expected: 0, new: 7 };
+
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &intruder.pubkey(), &cas);
+    let err = send(&mut context, &[ix], &[&intruder]).await.unwrap_err();
+    assert_missing_signature(err);
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 0);
+
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &cas);
+    send(&mut context, &[ix], &[&authority]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 7);
+}
+
+#[tokio::test]
+async fn set_authority_requires_the_stored_authority() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let takeover = CounterInstruction::SetAuthority { new_authority: intruder.pubkey() };
+
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &intruder.pubkey(), &takeover);
+    let err = send(&mut context, &[ix], &[&intruder]).await.unwrap_err();
+    assert_missing_signature(err);
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.authority, authority.pubkey());
+
+    let new_authority = Keypair::new();
+    let transfer = CounterInstruction::SetAuthority { new_authority: new_authority.pubkey() };
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &transfer);
+    send(&mut context, &[ix], &[&authority]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.authority, new_authority.pubkey());
+}
+
+#[tokio::test]
+async fn auth_decrement_by_requires_the_stored_authority() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let set = CounterInstruction::CompareAndSet { expected: 0, new: 10 };
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &set);
+    send(&mut context, &[ix], &[&authority]).await.unwrap();
+
+    let decrement = CounterInstruction::AuthDecrementBy { amount: 4 };
+
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &intruder.pubkey(), &decrement);
+    let err = send(&mut context, &[ix], &[&intruder]).await.unwrap_err();
+    assert_missing_signature(err);
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 10);
+
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &decrement);
+    send(&mut context, &[ix], &[&authority]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 6);
+}
+
+#[tokio::test]
+async fn auth_set_value_requires_the_stored_authority() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let set = CounterInstruction::AuthSetValue { value: 42 };
+
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &intruder.pubkey(), &set);
+    let err = send(&mut context, &[ix], &[&intruder]).await.unwrap_err();
+    assert_missing_signature(err);
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 0);
+
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &set);
+    send(&mut context, &[ix], &[&authority]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 42);
+}
+
+#[tokio::test]
+async fn increment_with_an_authority_account_requires_the_stored_authority() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let ix = increment_instruction_with_authority(&program_id, &counter.pubkey(), &intruder.pubkey());
+    let err = send(&mut context, &[ix], &[&intruder]).await.unwrap_err();
+    assert_missing_signature(err);
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 0);
+
+    let ix = increment_instruction_with_authority(&program_id, &counter.pubkey(), &authority.pubkey());
+    send(&mut context, &[ix], &[&authority]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 1);
+}
+
+#[tokio::test]
+async fn get_and_reset_returns_the_previous_count_and_clears_it() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let set = CounterInstruction::AuthSetValue { value: 9 };
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &set);
+    send(&mut context, &[ix], &[&authority]).await.unwrap();
+
+    let drain = CounterInstruction::GetAndReset;
+
+    let ix = authority_instruction(&program_id, &counter.pubkey(), &intruder.pubkey(), &drain);
+    let err = send(&mut context, &[ix], &[&intruder]).await.unwrap_err();
+    assert_missing_signature(err);
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 9);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[authority_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &drain)],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    let result = context.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    assert!(result.result.is_ok());
+    let return_data = result.metadata.unwrap().return_data.unwrap();
+    let mut expected = [0u8; std::mem::size_of::<Count>()];
+    expected[0] = 9;
+    assert_eq!(return_data.data, expected);
+
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 0);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use {
+    common::{get_counter, send, start},
+    rust_solana::{batch_counter_address, encode_label, initialize_batch_instruction, MAX_BATCH_INITIALIZE},
+    solana_program::{instruction::InstructionError, pubkey::Pubkey},
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+#[tokio::test]
+async fn initialize_batch_creates_every_derived_counter() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let base = Keypair::new();
+    let payer = context.payer.pubkey();
+
+    let label = encode_label("batch").unwrap();
+    let batch_ix = initialize_batch_instruction(&program_id, &payer, &base.pubkey(), 3, label);
+    send(&mut context, &[batch_ix], &[&base]).await.unwrap();
+
+    for index in 0..3 {
+        let (address, _) = batch_counter_address(&program_id, &base.pubkey(), index);
+        let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+        assert_eq!(account.owner, program_id);
+        let state = get_counter(&mut context.banks_client, &address).await;
+        assert_eq!(state.count, 0);
+        assert_eq!(state.authority, base.pubkey());
+        assert_eq!(state.label, label);
+    }
+    let (next, _) = batch_counter_address(&program_id, &base.pubkey(), 3);
+    assert!(context.banks_client.get_account(next).await.unwrap().is_none());
+
+    // 已经创建过的地址：整条指令失败
+    let batch_ix = initialize_batch_instruction(&program_id, &payer, &base.pubkey(), 4, label);
+    let err = send(&mut context, &[batch_ix], &[&base]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
+    assert!(context.banks_client.get_account(next).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn initialize_batch_validates_size_and_addresses() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let base = Keypair::new();
+    let payer = context.payer.pubkey();
+    let label = encode_label("batch").unwrap();
+
+    let oversized_ix = initialize_batch_instruction(&program_id, &payer, &base.pubkey(), MAX_BATCH_INITIALIZE + 1, label);
+    let err = send(&mut context, &[oversized_ix], &[&base]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+
+    // 用另一个base派生的地址
+    let mut wrong_ix = initialize_batch_instruction(&program_id, &payer, &base.pubkey(), 2, label);
+    wrong_ix.accounts[4].pubkey = batch_counter_address(&program_id, &Pubkey::new_unique(), 1).0;
+    let err = send(&mut context, &[wrong_ix], &[&base]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidSeeds));
+
+    // base 必须签名
+    let mut unsigned_ix = initialize_batch_instruction(&program_id, &payer, &base.pubkey(), 1, label);
+    unsigned_ix.accounts[1].is_signer = false;
+    let err = send(&mut context, &[unsigned_ix], &[]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+    let (first, _) = batch_counter_address(&program_id, &base.pubkey(), 0);
+    assert!(context.banks_client.get_account(first).await.unwrap().is_none());
+}
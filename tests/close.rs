@@ -0,0 +1,73 @@
+mod common;
+
+use {
+    common::{create_counter, program_test, send, start},
+    rust_solana::{close_instruction, LEGACY_COUNTER_LEN},
+    solana_program::{instruction::InstructionError, pubkey::Pubkey},
+    solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+#[tokio::test]
+async fn close_moves_all_lamports_and_removes_the_account() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+    let destination = Pubkey::new_unique();
+
+    let rent = context.banks_client.get_account(counter.pubkey()).await.unwrap().unwrap().lamports;
+    let close_ix = close_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &destination);
+    send(&mut context, &[close_ix], &[&authority]).await.unwrap();
+
+    // 没有lamports的账户在交易结束后被回收，再次读取不到任何数据
+    assert!(context.banks_client.get_account(counter.pubkey()).await.unwrap().is_none());
+    assert_eq!(context.banks_client.get_balance(destination).await.unwrap(), rent);
+}
+
+#[tokio::test]
+async fn close_rejects_a_read_only_destination() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let mut ix = close_instruction(&program_id, &counter.pubkey(), &authority.pubkey(), &Pubkey::new_unique());
+    ix.accounts[2].is_writable = false;
+
+    let err = send(&mut context, &[ix], &[&authority]).await.unwrap_err();
+    assert_eq!(
+        err.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+    assert!(context.banks_client.get_account(counter.pubkey()).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn close_rejects_legacy_accounts_without_an_authority() {
+    let program_id = Pubkey::new_unique();
+    let counter = Pubkey::new_unique();
+    let legacy = vec![1u8, 0x2a, 0x00, 0x00, 0x00];
+    assert_eq!(legacy.len(), LEGACY_COUNTER_LEN);
+    let mut program_test = program_test(program_id);
+    program_test.add_account(
+        counter,
+        Account { lamports: 1_000_000, data: legacy.clone(), owner: program_id, ..Account::default() },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // 旧布局没有授权账户，任何签名者都不能关闭它，账户和lamports保持不变
+    let authority = Keypair::new();
+    let close_ix = close_instruction(&program_id, &counter, &authority.pubkey(), &Pubkey::new_unique());
+    let err = send(&mut context, &[close_ix], &[&authority]).await.unwrap_err();
+    assert_eq!(
+        err.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    let account = context.banks_client.get_account(counter).await.unwrap().unwrap();
+    assert_eq!(account.data, legacy);
+    assert_eq!(account.lamports, 1_000_000);
+}
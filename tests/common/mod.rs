@@ -0,0 +1,121 @@
+// 集成测试共用的辅助函数：启动本地银行、创建并初始化计数器账户
+#![allow(dead_code)]
+
+use {
+    rust_solana::{encode_label, pack_instruction_data, process_instruction, Counter, CounterInstruction},
+    solana_program::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction},
+        program_pack::Pack,
+        pubkey::Pubkey,
+    },
+    solana_program_test::{processor, BanksClient, BanksClientError, ProgramTest, ProgramTestContext},
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    },
+    solana_system_interface::instruction as system_instruction,
+};
+
+// 使用固定的程序ID注册原生处理函数
+pub fn program_test(program_id: Pubkey) -> ProgramTest {
+    ProgramTest::new("rust_solana", program_id, processor!(process_instruction))
+}
+
+pub async fn start(program_id: Pubkey) -> ProgramTestContext {
+    program_test(program_id).start_with_context().await
+}
+
+// 用付款人签名发送一组指令，额外签名者按需传入
+pub async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+// 构造创建计数器账户（归属本程序）的指令
+pub async fn create_counter_account_ix(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    counter: &Pubkey,
+) -> Instruction {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    system_instruction::create_account(
+        &context.payer.pubkey(),
+        counter,
+        rent.minimum_balance(<Counter>::LEN),
+        <Counter>::LEN as u64,
+        program_id,
+    )
+}
+
+pub fn initialize_ix(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey, label: &str) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*counter, true), AccountMeta::new_readonly(*authority, true)],
+        data: pack_instruction_data(&CounterInstruction::Initialize {
+            label: encode_label(label).unwrap(),
+        }),
+    }
+}
+
+// 构造只需要计数器账户的指令（Increment、Decrement 等）
+pub fn counter_ix(program_id: &Pubkey, counter: &Pubkey, instruction: &CounterInstruction) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*counter, false)],
+        data: pack_instruction_data(instruction),
+    }
+}
+
+// 构造 DecrementAndPay 指令：payer 签名并向 recipient 转账
+pub fn decrement_and_pay_ix(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    amount: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: pack_instruction_data(&CounterInstruction::DecrementAndPay { amount }),
+    }
+}
+
+// 覆盖 Clock sysvar 的 unix_timestamp，模拟时间流逝
+pub async fn set_unix_timestamp(context: &mut ProgramTestContext, unix_timestamp: i64) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
+
+// 创建并初始化一个计数器，返回计数器账户的密钥对
+pub async fn create_counter(context: &mut ProgramTestContext, program_id: &Pubkey, authority: &Keypair) -> Keypair {
+    let counter = Keypair::new();
+    let create_ix = create_counter_account_ix(context, program_id, &counter.pubkey()).await;
+    let init_ix = initialize_ix(program_id, &counter.pubkey(), &authority.pubkey(), "test counter");
+    send(context, &[create_ix, init_ix], &[&counter, authority]).await.unwrap();
+    counter
+}
+
+pub async fn get_counter(banks_client: &mut BanksClient, counter: &Pubkey) -> Counter {
+    let account = banks_client.get_account(*counter).await.unwrap().expect("counter account exists");
+    Counter::unpack(&account.data).unwrap()
+}
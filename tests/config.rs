@@ -0,0 +1,74 @@
+mod common;
+
+use {
+    common::start,
+    rust_solana::{
+        decode_program_config, encode_program_config, pack_instruction_data, Count, Counter, CounterError, CounterInstruction,
+        ProgramConfig, ACCOUNT_VERSION, VERSION,
+    },
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    },
+    solana_program_test::ProgramTestContext,
+    solana_sdk::{
+        signature::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+};
+
+// 模拟一条指令，返回 return data 或交易错误
+async fn simulate(context: &mut ProgramTestContext, ix: Instruction) -> Result<Vec<u8>, TransactionError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction =
+        Transaction::new_signed_with_payer(&[ix], Some(&context.payer.pubkey()), &[&context.payer], blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    simulation.result.unwrap()?;
+    Ok(simulation.simulation_details.unwrap().return_data.map(|data| data.data).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn get_config_returns_the_compiled_constants() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+
+    let ix = Instruction {
+        program_id,
+        accounts: vec![],
+        data: pack_instruction_data(&CounterInstruction::GetConfig),
+    };
+    let data = simulate(&mut context, ix).await.unwrap();
+    let config = decode_program_config(&data).unwrap();
+    assert_eq!(config, ProgramConfig::current());
+    assert_eq!(config.counter_len as usize, Counter::<Count>::LEN);
+    assert_eq!(config.layout_version, ACCOUNT_VERSION);
+    assert_eq!(config.version, VERSION);
+
+    // 不接受任何账户
+    let ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(Pubkey::new_unique(), false)],
+        data: pack_instruction_data(&CounterInstruction::GetConfig),
+    };
+    let err = simulate(&mut context, ix).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::Custom(CounterError::TooManyAccounts as u32)));
+}
+
+#[test]
+fn program_config_layout_is_length_prefixed() {
+    let config = ProgramConfig { counter_len: 0x0198, layout_version: 9, version: "1.2.3".to_string() };
+    let data = encode_program_config(&config).unwrap();
+    assert_eq!(data, [0x98, 0x01, 9, 5, b'1', b'.', b'2', b'.', b'3']);
+    assert_eq!(decode_program_config(&data), Ok(config));
+
+    // 头部不完整、长度前缀与数据不符、版本不是UTF-8
+    assert_eq!(decode_program_config(&data[..3]), Err(ProgramError::InvalidArgument));
+    assert_eq!(decode_program_config(&data[..8]), Err(ProgramError::InvalidArgument));
+    assert_eq!(decode_program_config(&[data.as_slice(), b"x"].concat()), Err(ProgramError::InvalidArgument));
+    assert_eq!(decode_program_config(&[0, 0, 9, 1, 0xff]), Err(ProgramError::InvalidArgument));
+
+    let too_long = ProgramConfig { version: "v".repeat(256), ..ProgramConfig::current() };
+    assert_eq!(encode_program_config(&too_long), Err(ProgramError::InvalidArgument));
+}
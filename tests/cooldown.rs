@@ -0,0 +1,65 @@
+mod common;
+
+use {
+    common::{counter_ix, create_counter_account_ix, get_counter, send, set_unix_timestamp, start},
+    rust_solana::{encode_label, pack_instruction_data, CounterError, CounterInstruction},
+    solana_program::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+#[tokio::test]
+async fn cooldown_rejects_increments_until_the_interval_has_passed() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = Keypair::new();
+
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await;
+    let init_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), true), AccountMeta::new_readonly(authority.pubkey(), true)],
+        data: pack_instruction_data(&CounterInstruction::InitializeWithCooldown {
+            min_seconds_between: 60,
+            label: encode_label("cooldown").unwrap(),
+        }),
+    };
+    send(&mut context, &[create_ix, init_ix], &[&counter, &authority]).await.unwrap();
+
+    // 固定当前时间，之后断言 last_ts 时不受slot推进带来的时钟变化影响
+    let now = context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    set_unix_timestamp(&mut context, now).await;
+    let increment_ix = counter_ix(&program_id, &counter.pubkey(), &CounterInstruction::Increment);
+    send(&mut context, &[increment_ix], &[]).await.unwrap();
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert_eq!(state.count, 1);
+    assert_eq!(state.last_ts, now);
+
+    // 间隔不足60秒：自增被拒绝，减少计数不受影响
+    set_unix_timestamp(&mut context, now + 59).await;
+    let too_soon_ix = counter_ix(&program_id, &counter.pubkey(), &CounterInstruction::IncrementIfBelow { threshold: 10 });
+    let err = send(&mut context, &[too_soon_ix], &[]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::Custom(CounterError::TooSoon as u32)));
+    let decrement_ix = counter_ix(&program_id, &counter.pubkey(), &CounterInstruction::Decrement);
+    send(&mut context, &[decrement_ix], &[]).await.unwrap();
+
+    set_unix_timestamp(&mut context, now + 60).await;
+    let sequence = CounterInstruction::IncrementSequence { amounts: vec![2] };
+    let increment_ix = counter_ix(&program_id, &counter.pubkey(), &sequence);
+    send(&mut context, &[increment_ix], &[]).await.unwrap();
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert_eq!(state.count, 2);
+    assert_eq!(state.last_ts, now + 60);
+
+    // 空序列不算一次自增：不受冷却限制，也不更新 last_ts 和 total_ops
+    set_unix_timestamp(&mut context, now + 61).await;
+    let empty = CounterInstruction::IncrementSequence { amounts: vec![] };
+    send(&mut context, &[counter_ix(&program_id, &counter.pubkey(), &empty)], &[]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await, state);
+}
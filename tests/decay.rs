@@ -0,0 +1,84 @@
+mod common;
+
+use {
+    common::{create_counter_account_ix, get_counter, send, start},
+    rust_solana::{authority_instruction, encode_label, pack_instruction_data, Count, CounterInstruction},
+    solana_program::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    },
+    solana_program_test::ProgramTestContext,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    },
+};
+
+async fn set_slot(context: &mut ProgramTestContext, slot: u64) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.slot = slot;
+    context.set_sysvar(&clock);
+}
+
+#[tokio::test]
+async fn decaying_counter_settles_before_mutations_and_reads() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = Keypair::new();
+
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await;
+    let init_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), true), AccountMeta::new_readonly(authority.pubkey(), true)],
+        data: pack_instruction_data(&CounterInstruction::InitializeWithDecay {
+            decay_per_slot: 3,
+            label: encode_label("decaying").unwrap(),
+        }),
+    };
+    send(&mut context, &[create_ix, init_ix], &[&counter, &authority]).await.unwrap();
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert_eq!(state.decay_per_slot, 3);
+    let start_slot = state.last_slot;
+
+    // 同一个slot内不衰减
+    let set_ix = authority_instruction(
+        &program_id,
+        &counter.pubkey(),
+        &authority.pubkey(),
+        &CounterInstruction::AuthSetValue { value: 100 },
+    );
+    send(&mut context, &[set_ix], &[&authority]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 100);
+
+    // 10个slot后先结算衰减30，再自增
+    set_slot(&mut context, start_slot + 10).await;
+    let increment_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), false)],
+        data: pack_instruction_data(&CounterInstruction::Increment),
+    };
+    send(&mut context, &[increment_ix], &[]).await.unwrap();
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert_eq!(state.count, 71);
+    assert_eq!(state.last_slot, start_slot + 10);
+
+    // 只读指令返回结算后的计数，衰减最低到0
+    set_slot(&mut context, start_slot + 40).await;
+    let stats_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(counter.pubkey(), false)],
+        data: pack_instruction_data(&CounterInstruction::GetStats),
+    };
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction =
+        Transaction::new_signed_with_payer(&[stats_ix], Some(&context.payer.pubkey()), &[&context.payer], blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    let stats = simulation.simulation_details.unwrap().return_data.unwrap().data;
+    assert_eq!(stats[1..1 + std::mem::size_of::<Count>()], [0u8; std::mem::size_of::<Count>()]);
+    // 最后8字节是结算到的slot
+    assert_eq!(stats[stats.len() - 8..], (start_slot + 40).to_le_bytes());
+    // 只读指令不写回账户
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 71);
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use {
+    common::{create_counter, get_counter, send, start},
+    rust_solana::{pack_instruction_data, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+#[tokio::test]
+async fn decrement_at_zero_is_rejected_without_changing_state() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 0);
+
+    let decrement_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), false)],
+        data: pack_instruction_data(&CounterInstruction::Decrement),
+    };
+    let err = send(&mut context, &[decrement_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        err.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // 失败的交易不能留下任何修改
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert_eq!(state.count, 0);
+    assert!(state.is_initialized);
+    assert_eq!(state.authority, authority.pubkey());
+    // 只有初始化计入操作次数
+    assert_eq!(state.total_ops, 1);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use {
+    common::{create_counter, send, start},
+    rust_solana::{encode_label, pack_instruction_data, CounterError, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+// 向程序发送给定的原始指令数据，返回交易错误
+async fn send_raw(data: Vec<u8>) -> TransactionError {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let counter = create_counter(&mut context, &program_id, &Keypair::new()).await;
+
+    let ix = Instruction { program_id, accounts: vec![AccountMeta::new(counter.pubkey(), false)], data };
+    send(&mut context, &[ix], &[]).await.unwrap_err().unwrap()
+}
+
+#[tokio::test]
+async fn entrypoint_rejects_empty_instruction_data() {
+    assert_eq!(
+        send_raw(vec![]).await,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+}
+
+#[tokio::test]
+async fn entrypoint_rejects_an_unknown_opcode() {
+    assert_eq!(
+        send_raw(vec![0, 99]).await,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+}
+
+#[tokio::test]
+async fn entrypoint_checks_the_account_count() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    // Decrement 只使用计数器账户，多出的账户说明客户端用错了账户列表
+    let decrement_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(counter.pubkey(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        ],
+        data: pack_instruction_data(&CounterInstruction::Decrement),
+    };
+    let err = send(&mut context, &[decrement_ix], &[]).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(CounterError::TooManyAccounts as u32))
+    );
+
+    // Relabel 需要授权账户
+    let relabel_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), false)],
+        data: pack_instruction_data(&CounterInstruction::Relabel { label: encode_label("renamed").unwrap() }),
+    };
+    let err = send(&mut context, &[relabel_ix], &[]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys));
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use {
+    common::{create_counter, get_counter, send, start},
+    rust_solana::{pack_instruction_data, CounterInstruction},
+    solana_program::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    },
+    solana_sdk::signature::{Keypair, Signer},
+};
+
+#[tokio::test]
+async fn increment_by_epoch_adds_the_current_epoch() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.epoch = 42;
+    context.set_sysvar(&clock);
+
+    let ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), false)],
+        data: pack_instruction_data(&CounterInstruction::IncrementByEpoch),
+    };
+    send(&mut context, &[ix], &[]).await.unwrap();
+
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 42);
+}
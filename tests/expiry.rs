@@ -0,0 +1,122 @@
+mod common;
+
+use {
+    common::{counter_ix, create_counter_account_ix, get_counter, send, set_unix_timestamp, start},
+    rust_solana::{encode_label, pack_instruction_data, CounterError, CounterInstruction},
+    solana_program::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    solana_program_test::ProgramTestBanksClientExt,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+#[tokio::test]
+async fn expired_counter_rejects_mutations_but_can_be_closed() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = Keypair::new();
+
+    let now = context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    let expires_at = now + 100;
+
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await;
+    let init_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), true), AccountMeta::new_readonly(authority.pubkey(), true)],
+        data: pack_instruction_data(&CounterInstruction::InitializeWithExpiry {
+            expires_at,
+            label: encode_label("expiring").unwrap(),
+        }),
+    };
+    send(&mut context, &[create_ix, init_ix], &[&counter, &authority]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.expires_at, expires_at);
+
+    // 过期前可以正常修改
+    send(&mut context, &[counter_ix(&program_id, &counter.pubkey(), &CounterInstruction::Increment)], &[]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 1);
+
+    // 等到出现新的区块哈希，否则与上一笔完全相同的交易会被当作重复交易，直接返回上一笔的结果。
+    // context.get_new_latest_blockhash 比较的是启动时的哈希，不一定比上一笔交易用的新，因此显式比较。
+    // 时间在换哈希之后再设置，避免被覆盖
+    let previous = context.banks_client.get_latest_blockhash().await.unwrap();
+    context.banks_client.get_new_latest_blockhash(&previous).await.unwrap();
+    set_unix_timestamp(&mut context, expires_at).await;
+    let err = send(&mut context, &[counter_ix(&program_id, &counter.pubkey(), &CounterInstruction::Increment)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CounterError::Expired as u32))
+    );
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 1);
+
+    // 过期后仍然可以关闭并取回租金
+    let destination = Pubkey::new_unique();
+    let close_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(counter.pubkey(), false),
+            AccountMeta::new_readonly(authority.pubkey(), true),
+            AccountMeta::new(destination, false),
+        ],
+        data: pack_instruction_data(&CounterInstruction::Close),
+    };
+    send(&mut context, &[close_ix], &[&authority]).await.unwrap();
+    assert!(context.banks_client.get_account(counter.pubkey()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn decrement_all_skips_expired_counters_when_skip_invalid_is_set() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let expiring = Keypair::new();
+    let live = Keypair::new();
+
+    let now = context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    let expires_at = now + 100;
+
+    let mut instructions = vec![];
+    for (counter, expires_at) in [(&expiring, expires_at), (&live, 0)] {
+        instructions.push(create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await);
+        instructions.push(Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(counter.pubkey(), true),
+                AccountMeta::new_readonly(authority.pubkey(), true),
+            ],
+            data: pack_instruction_data(&CounterInstruction::InitializeWithExpiry {
+                expires_at,
+                label: encode_label("batch").unwrap(),
+            }),
+        });
+        instructions.push(counter_ix(&program_id, &counter.pubkey(), &CounterInstruction::Increment));
+    }
+    send(&mut context, &instructions, &[&expiring, &live, &authority]).await.unwrap();
+
+    set_unix_timestamp(&mut context, expires_at).await;
+    let decrement_all_ix = |skip_invalid| Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(expiring.pubkey(), false), AccountMeta::new(live.pubkey(), false)],
+        data: pack_instruction_data(&CounterInstruction::DecrementAll { skip_invalid }),
+    };
+
+    // 不跳过时过期的计数器让整批失败
+    let err = send(&mut context, &[decrement_all_ix(false)], &[]).await.unwrap_err();
+    assert_eq!(
+        err.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CounterError::Expired as u32))
+    );
+    assert_eq!(get_counter(&mut context.banks_client, &live.pubkey()).await.count, 1);
+
+    // 跳过时只减少未过期的计数器
+    send(&mut context, &[decrement_all_ix(true)], &[]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &expiring.pubkey()).await.count, 1);
+    assert_eq!(get_counter(&mut context.banks_client, &live.pubkey()).await.count, 0);
+}
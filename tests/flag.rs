@@ -0,0 +1,51 @@
+mod common;
+
+use {
+    common::{create_counter, get_counter, program_test, send},
+    rust_solana::{pack_instruction_data, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+fn increment_if_flag_ix(program_id: &Pubkey, counter: &Pubkey, flag_account: &Pubkey, offset: u32) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*counter, false), AccountMeta::new_readonly(*flag_account, false)],
+        data: pack_instruction_data(&CounterInstruction::IncrementIfFlag { offset }),
+    }
+}
+
+#[tokio::test]
+async fn increment_if_flag_follows_the_flag_byte() {
+    let program_id = Pubkey::new_unique();
+    let flag_account = Pubkey::new_unique();
+    let mut program_test = program_test(program_id);
+    // 标志账户属于其他程序，第0字节为0，第1字节非零
+    let owner = Pubkey::new_unique();
+    program_test.add_account(
+        flag_account,
+        Account { lamports: 1_000_000_000, data: vec![0, 1], owner, executable: false, rent_epoch: 0 },
+    );
+    let mut context = program_test.start_with_context().await;
+    let counter = create_counter(&mut context, &program_id, &Keypair::new()).await;
+
+    let clear_ix = increment_if_flag_ix(&program_id, &counter.pubkey(), &flag_account, 0);
+    send(&mut context, &[clear_ix], &[]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 0);
+
+    let set_ix = increment_if_flag_ix(&program_id, &counter.pubkey(), &flag_account, 1);
+    send(&mut context, &[set_ix], &[]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 1);
+
+    let out_of_bounds_ix = increment_if_flag_ix(&program_id, &counter.pubkey(), &flag_account, 2);
+    let err = send(&mut context, &[out_of_bounds_ix], &[]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 1);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use {
+    common::{initialize_ix, program_test, send, start},
+    rust_solana::{
+        counter_account_space, encode_label, pack_instruction_data, Counter, CounterInstruction, MAX_ACCOUNT_PADDING,
+    },
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program_pack::Pack,
+        pubkey::Pubkey,
+    },
+    solana_program_test::ProgramTestContext,
+    solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+    solana_system_interface::instruction as system_instruction,
+};
+
+#[tokio::test]
+async fn initialize_rejects_an_account_owned_by_another_program() {
+    let program_id = Pubkey::new_unique();
+    let counter = Keypair::new();
+
+    // 直接构造一个大小正确、但归属系统程序的账户
+    let mut program_test = program_test(program_id);
+    program_test.add_account(
+        counter.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            data: vec![0u8; <Counter>::LEN],
+            owner: solana_system_interface::program::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let init_ix = initialize_ix(&program_id, &counter.pubkey(), &authority.pubkey(), "foreign");
+    let err = send(&mut context, &[init_ix], &[&counter, &authority]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IncorrectProgramId));
+
+    // 账户保持原样
+    let account = context.banks_client.get_account(counter.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.owner, solana_system_interface::program::ID);
+    assert!(account.data.iter().all(|&b| b == 0));
+}
+
+// 创建大小为 space 的计数器账户并用 InitializeWithPadding 初始化
+async fn initialize_padded(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    space: usize,
+    extra: u16,
+) -> Result<Keypair, TransactionError> {
+    let counter = Keypair::new();
+    let authority = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let create_ix = system_instruction::create_account(
+        &context.payer.pubkey(),
+        &counter.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        program_id,
+    );
+    let init_ix = Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), true), AccountMeta::new_readonly(authority.pubkey(), true)],
+        data: pack_instruction_data(&CounterInstruction::InitializeWithPadding {
+            extra,
+            label: encode_label("padded").unwrap(),
+        }),
+    };
+    send(context, &[create_ix, init_ix], &[&counter, &authority]).await.map_err(|err| err.unwrap())?;
+    Ok(counter)
+}
+
+#[tokio::test]
+async fn initialize_with_padding_accepts_an_oversized_account() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+
+    let counter = initialize_padded(&mut context, &program_id, counter_account_space(64), 64).await.unwrap();
+
+    let account = context.banks_client.get_account(counter.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), <Counter>::LEN + 64);
+    let state = <Counter>::unpack_lenient(&account.data).unwrap();
+    assert_eq!(state.label, encode_label("padded").unwrap());
+    assert!(account.data[<Counter>::LEN..].iter().all(|&b| b == 0));
+}
+
+#[tokio::test]
+async fn initialize_with_padding_rejects_bad_sizes() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+
+    // 账户比声明的预留空间小
+    let err = initialize_padded(&mut context, &program_id, counter_account_space(8), 64).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(1, InstructionError::InvalidAccountData));
+
+    // 预留超过上限
+    let extra = MAX_ACCOUNT_PADDING + 1;
+    let err = initialize_padded(&mut context, &program_id, counter_account_space(extra), extra).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(1, InstructionError::InvalidArgument));
+}
@@ -0,0 +1,42 @@
+use {
+    rust_solana::{
+        encode_label, pack_instruction_data, pack_instruction_data_strict, unpack_instruction_data, CounterInstruction,
+        STRICT_INSTRUCTION_FORMAT_VERSION,
+    },
+    solana_program::program_error::ProgramError,
+};
+
+#[test]
+fn relaxed_format_ignores_trailing_bytes() {
+    let mut data = pack_instruction_data(&CounterInstruction::Increment);
+    data.push(0xff);
+    assert_eq!(unpack_instruction_data(&data), Ok(CounterInstruction::Increment));
+}
+
+#[test]
+fn strict_format_rejects_trailing_bytes() {
+    let mut data = pack_instruction_data_strict(&CounterInstruction::Increment);
+    data.push(0xff);
+    assert_eq!(unpack_instruction_data(&data), Err(ProgramError::InvalidInstructionData));
+
+    let mut data = pack_instruction_data_strict(&CounterInstruction::Spend { amount: 3 });
+    data.push(0);
+    assert_eq!(unpack_instruction_data(&data), Err(ProgramError::InvalidInstructionData));
+}
+
+#[test]
+fn strict_format_accepts_canonical_encodings() {
+    let instructions = [
+        CounterInstruction::Increment,
+        CounterInstruction::Spend { amount: 3 },
+        CounterInstruction::Initialize { label: encode_label("strict").unwrap() },
+        CounterInstruction::IncrementSequence { amounts: vec![1, 2, 3] },
+        CounterInstruction::InitializeWithSeed { seed: "seed".to_string(), label: encode_label("strict").unwrap() },
+        CounterInstruction::InitializeBatch { count: 2, label: encode_label("strict").unwrap() },
+    ];
+    for instruction in instructions {
+        let data = pack_instruction_data_strict(&instruction);
+        assert_eq!(data[0], STRICT_INSTRUCTION_FORMAT_VERSION);
+        assert_eq!(unpack_instruction_data(&data), Ok(instruction));
+    }
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use {
+    common::{counter_ix, create_counter_account_ix, get_counter, send, start},
+    rust_solana::{encode_label, pack_instruction_data, CounterError, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+#[tokio::test]
+async fn lifetime_limit_counts_every_operation_after_initialize() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = Keypair::new();
+
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await;
+    let init_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), true), AccountMeta::new_readonly(authority.pubkey(), true)],
+        data: pack_instruction_data(&CounterInstruction::InitializeWithLifetimeLimit {
+            lifetime_limit: 3,
+            label: encode_label("quota").unwrap(),
+        }),
+    };
+    send(&mut context, &[create_ix, init_ix], &[&counter, &authority]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.lifetime_limit, 3);
+
+    // 自增、减少、再自增，共用掉3次额度
+    for instruction in [
+        CounterInstruction::Increment,
+        CounterInstruction::Decrement,
+        CounterInstruction::IncrementIfBelow { threshold: 10 },
+    ] {
+        send(&mut context, &[counter_ix(&program_id, &counter.pubkey(), &instruction)], &[]).await.unwrap();
+    }
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 1);
+
+    // 当前计数很低，但额度已经用完
+    let increment_ix = counter_ix(&program_id, &counter.pubkey(), &CounterInstruction::Increment);
+    let err = send(&mut context, &[increment_ix], &[]).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(CounterError::LifetimeExhausted as u32))
+    );
+
+    // 减少计数不受限制
+    let decrement_ix = counter_ix(&program_id, &counter.pubkey(), &CounterInstruction::DecrementByReport { amount: 1 });
+    send(&mut context, &[decrement_ix], &[]).await.unwrap();
+    assert_eq!(get_counter(&mut context.banks_client, &counter.pubkey()).await.count, 0);
+}
@@ -0,0 +1,84 @@
+use {
+    rust_solana::math::{add, add_capped, rem, sub, sub_floor},
+    solana_program::program_error::ProgramError,
+};
+
+const MAX: u32 = u32::MAX;
+
+#[test]
+fn add_fails_only_past_the_type_maximum() {
+    assert_eq!(add(0u32, 0), Ok(0));
+    assert_eq!(add(0u32, 1), Ok(1));
+    assert_eq!(add(1u32, MAX - 1), Ok(MAX));
+    assert_eq!(add(MAX - 1, 1), Ok(MAX));
+    assert_eq!(add(MAX, 0), Ok(MAX));
+    assert_eq!(add(MAX, 1), Err(ProgramError::ArithmeticOverflow));
+    assert_eq!(add(MAX - 1, 2), Err(ProgramError::ArithmeticOverflow));
+    assert_eq!(add(MAX, MAX), Err(ProgramError::ArithmeticOverflow));
+
+    // u128 计数与 total_ops 使用的u64边界相同
+    assert_eq!(add(u128::MAX - 1, 1), Ok(u128::MAX));
+    assert_eq!(add(u128::MAX, 1), Err(ProgramError::ArithmeticOverflow));
+    assert_eq!(add(u64::MAX, 1), Err(ProgramError::ArithmeticOverflow));
+}
+
+#[test]
+fn sub_fails_only_below_zero() {
+    assert_eq!(sub(0u32, 0), Ok(0));
+    assert_eq!(sub(1u32, 1), Ok(0));
+    assert_eq!(sub(MAX, MAX), Ok(0));
+    assert_eq!(sub(MAX, MAX - 1), Ok(1));
+    assert_eq!(sub(MAX, 1), Ok(MAX - 1));
+    assert_eq!(sub(0u32, 1), Err(ProgramError::ArithmeticOverflow));
+    assert_eq!(sub(MAX - 1, MAX), Err(ProgramError::ArithmeticOverflow));
+    assert_eq!(sub(0u128, 1), Err(ProgramError::ArithmeticOverflow));
+}
+
+#[test]
+fn add_capped_stops_at_the_cap() {
+    let cap = 100u32;
+    assert_eq!(add_capped(0, 0, cap), 0);
+    assert_eq!(add_capped(0, 1, cap), 1);
+    assert_eq!(add_capped(cap - 2, 1, cap), cap - 1);
+    assert_eq!(add_capped(cap - 1, 1, cap), cap);
+    assert_eq!(add_capped(cap - 1, 2, cap), cap);
+    assert_eq!(add_capped(cap, 1, cap), cap);
+    assert_eq!(add_capped(cap, 0, cap), cap);
+    // 已经超过上限的计数保持不变
+    assert_eq!(add_capped(cap + 1, 1, cap), cap + 1);
+    // 加法溢出时得到上限，而不是报错
+    assert_eq!(add_capped(1, MAX, MAX), MAX);
+    assert_eq!(add_capped(MAX - 1, MAX, MAX), MAX);
+    assert_eq!(add_capped(MAX - 1, 1, MAX - 1), MAX - 1);
+    assert_eq!(add_capped(MAX, 1, MAX), MAX);
+    assert_eq!(add_capped(5u32, 10, 0), 5);
+}
+
+#[test]
+fn sub_floor_stops_at_zero() {
+    assert_eq!(sub_floor(0u32, 0), 0);
+    assert_eq!(sub_floor(0u32, 1), 0);
+    assert_eq!(sub_floor(1u32, 1), 0);
+    assert_eq!(sub_floor(2u32, 1), 1);
+    assert_eq!(sub_floor(MAX, 1), MAX - 1);
+    assert_eq!(sub_floor(MAX, MAX as u128), 0);
+    assert_eq!(sub_floor(MAX, MAX as u128 - 1), 1);
+    // delta 超出u32范围
+    assert_eq!(sub_floor(MAX, MAX as u128 + 1), 0);
+    assert_eq!(sub_floor(MAX, u128::MAX), 0);
+    assert_eq!(sub_floor(u128::MAX, u128::MAX - 1), 1);
+}
+
+#[test]
+fn rem_rejects_a_zero_divisor() {
+    assert_eq!(rem(0u32, 0), Err(ProgramError::InvalidArgument));
+    assert_eq!(rem(MAX, 0), Err(ProgramError::InvalidArgument));
+    assert_eq!(rem(0u32, 1), Ok(0));
+    assert_eq!(rem(MAX, 1), Ok(0));
+    assert_eq!(rem(MAX - 1, MAX), Ok(MAX - 1));
+    assert_eq!(rem(MAX, MAX), Ok(0));
+    assert_eq!(rem(7u32, 3), Ok(1));
+    // u128 计数的余数同样小于divisor
+    assert_eq!(rem(u128::MAX, MAX), Ok((u128::MAX % MAX as u128) as u32));
+    assert_eq!(rem(u128::MAX, 0), Err(ProgramError::InvalidArgument));
+}
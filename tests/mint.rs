@@ -0,0 +1,77 @@
+mod common;
+
+use {
+    common::{create_counter, create_counter_account_ix, get_counter, send, start},
+    rust_solana::{encode_label, pack_instruction_data, Count, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    },
+    solana_program_test::ProgramTestContext,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    },
+};
+
+// 模拟一条只读指令并返回它的 return data
+async fn simulate_read(
+    context: &mut ProgramTestContext,
+    program_id: Pubkey,
+    counter: Pubkey,
+    instruction: &CounterInstruction,
+) -> Vec<u8> {
+    let ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(counter, false)],
+        data: pack_instruction_data(instruction),
+    };
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction =
+        Transaction::new_signed_with_payer(&[ix], Some(&context.payer.pubkey()), &[&context.payer], blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    simulation.simulation_details.unwrap().return_data.unwrap().data
+}
+
+#[tokio::test]
+async fn initialize_with_mint_stores_and_returns_the_mint() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let counter = Keypair::new();
+    let authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await;
+    let init_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter.pubkey(), true), AccountMeta::new_readonly(authority.pubkey(), true)],
+        data: pack_instruction_data(&CounterInstruction::InitializeWithMint {
+            mint,
+            label: encode_label("token").unwrap(),
+        }),
+    };
+    send(&mut context, &[create_ix, init_ix], &[&counter, &authority]).await.unwrap();
+
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert_eq!(state.mint, mint);
+    assert_eq!(state.label, encode_label("token").unwrap());
+
+    let returned = simulate_read(&mut context, program_id, counter.pubkey(), &CounterInstruction::GetMint).await;
+    assert_eq!(returned, mint.to_bytes());
+
+    // GetStats 中 total_ops 之后的32字节是mint
+    let stats = simulate_read(&mut context, program_id, counter.pubkey(), &CounterInstruction::GetStats).await;
+    let offset = 9 + std::mem::size_of::<Count>();
+    assert_eq!(stats[offset..offset + 32], mint.to_bytes());
+}
+
+#[tokio::test]
+async fn plain_initialize_leaves_the_mint_unset() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let returned = simulate_read(&mut context, program_id, counter.pubkey(), &CounterInstruction::GetMint).await;
+    assert_eq!(returned, Pubkey::default().to_bytes());
+}
@@ -0,0 +1,239 @@
+// MockRpc 和 counter_client 模块属于 client feature
+#![cfg(feature = "client")]
+
+use {
+    rust_solana::{
+        counter_client::{
+            estimate_flow_cost, fetch_counters, rent_status, wait_for_confirmations, Confirmations, CounterLookup,
+            CounterRpc, FailoverRpc, FlowCost, MockRpc, RentStatus, Simulation, MAX_MULTIPLE_ACCOUNTS,
+        },
+        pack_instruction_data, unpack_instruction_data, Counter, CounterInstruction,
+    },
+    solana_program::{
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        rent::Rent,
+    },
+    solana_sdk::{
+        account::Account,
+        commitment_config::CommitmentConfig,
+        signature::{Keypair, Signature, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    std::{cell::RefCell, time::Duration},
+};
+
+#[test]
+fn mock_rpc_serves_preloaded_counter_accounts() {
+    let program_id = Pubkey::new_unique();
+    let counter_pubkey = Pubkey::new_unique();
+    let counter: Counter = Counter { total_ops: 3, ..Counter::new(3).with_authority(Pubkey::new_unique()) };
+    let mut data = vec![0u8; <Counter>::LEN];
+    Counter::pack(counter.clone(), &mut data).unwrap();
+
+    let rpc = MockRpc::new(Hash::new_unique());
+    rpc.set_account(
+        counter_pubkey,
+        Account { lamports: 1_000, data, owner: program_id, executable: false, rent_epoch: 0 },
+    );
+
+    let data = rpc.get_account_data(&counter_pubkey).unwrap();
+    assert_eq!(<Counter>::unpack_lenient(&data).unwrap(), counter);
+    assert_eq!(rpc.get_account(&counter_pubkey).unwrap().unwrap().owner, program_id);
+    assert_eq!(rpc.get_balance(&counter_pubkey).unwrap(), 1_000);
+
+    let blockhash = rpc.get_latest_blockhash().unwrap();
+    assert!(rpc.is_blockhash_valid(&blockhash).unwrap());
+    assert!(!rpc.is_blockhash_valid(&Hash::new_unique()).unwrap());
+
+    let missing = Pubkey::new_unique();
+    assert!(rpc.get_account(&missing).unwrap().is_none());
+    assert!(rpc.get_account_data(&missing).is_err());
+    assert_eq!(rpc.get_balance(&missing).unwrap(), 0);
+}
+
+#[test]
+fn mock_rpc_records_sent_transactions() {
+    let program_id = Pubkey::new_unique();
+    let counter_pubkey = Pubkey::new_unique();
+    let payer = Keypair::new();
+    let rpc = MockRpc::new(Hash::new_unique());
+
+    let increment_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter_pubkey, false)],
+        data: pack_instruction_data(&CounterInstruction::Increment),
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[increment_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        rpc.get_latest_blockhash().unwrap(),
+    );
+    let signature = rpc.send_and_confirm_transaction(&transaction, CommitmentConfig::confirmed()).unwrap();
+
+    let sent = rpc.sent_transactions();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].signatures[0], signature);
+    let instruction = &sent[0].message.instructions[0];
+    assert_eq!(unpack_instruction_data(&instruction.data), Ok(CounterInstruction::Increment));
+}
+
+#[test]
+fn estimate_flow_cost_reads_per_instruction_units_from_the_logs() {
+    let program_id = Pubkey::new_unique();
+    let payer = Keypair::new();
+    let rpc = MockRpc::new(Hash::new_unique());
+    rpc.set_simulation(Simulation {
+        err: None,
+        logs: vec![
+            "Program 11111111111111111111111111111111 invoke [1]".to_string(),
+            "Program 11111111111111111111111111111111 success".to_string(),
+            format!("Program {} invoke [1]", program_id),
+            format!("Program {} consumed 2100 of 1399850 compute units", program_id),
+            format!("Program {} success", program_id),
+            format!("Program {} invoke [1]", program_id),
+            format!("Program {} consumed 900 of 1397750 compute units", program_id),
+            format!("Program {} success", program_id),
+        ],
+        units_consumed: 3150,
+    });
+
+    let cost = estimate_flow_cost(&rpc, &payer, &program_id).unwrap();
+    assert_eq!(cost, FlowCost { initialize: 2100, increment: 900, total: 3150 });
+    // 只模拟，不发送任何交易
+    assert!(rpc.sent_transactions().is_empty());
+}
+
+#[test]
+fn estimate_flow_cost_fails_when_the_simulation_fails() {
+    let rpc = MockRpc::new(Hash::new_unique());
+    rpc.set_simulation(Simulation {
+        err: Some(TransactionError::AccountNotFound),
+        ..Simulation::default()
+    });
+    assert!(estimate_flow_cost(&rpc, &Keypair::new(), &Pubkey::new_unique()).is_err());
+}
+
+#[test]
+fn failover_rpc_retries_connection_errors_on_the_fallback() {
+    let pubkey = Pubkey::new_unique();
+    let primary = MockRpc::new(Hash::new_unique()).with_url("http://primary");
+    let fallback = MockRpc::new(Hash::new_unique()).with_url("http://fallback");
+    fallback.set_account(pubkey, Account { lamports: 42, ..Account::default() });
+    let logs = RefCell::new(Vec::new());
+    let rpc = FailoverRpc::new(&primary, &fallback, |message| logs.borrow_mut().push(message.to_string()));
+
+    // 主节点正常响应时不使用备用节点，即使返回的是"账户不存在"之类的错误
+    assert_eq!(rpc.get_balance(&pubkey).unwrap(), 0);
+    assert!(rpc.get_account_data(&pubkey).is_err());
+    assert_eq!(logs.borrow().last().unwrap(), "getAccountInfo 由 http://primary 处理");
+
+    primary.set_unreachable(true);
+    assert_eq!(rpc.get_balance(&pubkey).unwrap(), 42);
+    assert_eq!(logs.borrow().last().unwrap(), "getBalance 由 http://fallback 处理");
+
+    // 两个节点都无法连接时返回备用节点的错误
+    fallback.set_unreachable(true);
+    let err = rpc.get_latest_blockhash().unwrap_err();
+    assert!(err.to_string().contains("http://fallback"));
+}
+
+#[test]
+fn failover_rpc_sends_the_same_transaction_to_the_fallback() {
+    let payer = Keypair::new();
+    let primary = MockRpc::new(Hash::new_unique());
+    let fallback = MockRpc::new(Hash::new_unique());
+    primary.set_unreachable(true);
+    let rpc = FailoverRpc::new(&primary, &fallback, |_| {});
+
+    let transaction = Transaction::new_signed_with_payer(&[], Some(&payer.pubkey()), &[&payer], Hash::new_unique());
+    let signature = rpc.send_and_confirm_transaction(&transaction, CommitmentConfig::confirmed()).unwrap();
+    assert_eq!(signature, transaction.signatures[0]);
+    assert!(primary.sent_transactions().is_empty());
+    assert_eq!(fallback.sent_transactions(), vec![transaction]);
+}
+
+#[test]
+fn fetch_counters_reports_each_account_separately() {
+    let program_id = Pubkey::new_unique();
+    let counter: Counter = Counter { total_ops: 1, ..Counter::new(9).with_authority(Pubkey::new_unique()) };
+    let mut data = vec![0u8; <Counter>::LEN];
+    Counter::pack(counter.clone(), &mut data).unwrap();
+
+    let rpc = MockRpc::new(Hash::new_unique());
+    let (valid, missing, foreign, uninitialized) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let other_program = Pubkey::new_unique();
+    rpc.set_account(valid, Account { lamports: 1, data: data.clone(), owner: program_id, ..Account::default() });
+    rpc.set_account(foreign, Account { lamports: 1, data, owner: other_program, ..Account::default() });
+    rpc.set_account(
+        uninitialized,
+        Account { lamports: 1, data: vec![0u8; <Counter>::LEN], owner: program_id, ..Account::default() },
+    );
+
+    let lookups = fetch_counters(&rpc, &program_id, &[valid, missing, foreign, uninitialized]).unwrap();
+    assert_eq!(
+        lookups,
+        vec![
+            CounterLookup::Counter(counter),
+            CounterLookup::Missing,
+            CounterLookup::WrongOwner(other_program),
+            CounterLookup::Invalid(ProgramError::UninitializedAccount),
+        ]
+    );
+}
+
+#[test]
+fn fetch_counters_splits_large_requests() {
+    let rpc = MockRpc::new(Hash::new_unique());
+    let pubkeys: Vec<Pubkey> = (0..MAX_MULTIPLE_ACCOUNTS + 1).map(|_| Pubkey::new_unique()).collect();
+    // MockRpc 与真实节点一样拒绝超过 MAX_MULTIPLE_ACCOUNTS 个地址的单次请求
+    assert!(rpc.get_multiple_accounts(&pubkeys).is_err());
+
+    let lookups = fetch_counters(&rpc, &Pubkey::new_unique(), &pubkeys).unwrap();
+    assert_eq!(lookups, vec![CounterLookup::Missing; MAX_MULTIPLE_ACCOUNTS + 1]);
+}
+
+#[test]
+fn rent_status_uses_the_account_data_length() {
+    let rpc = MockRpc::new(Hash::new_unique());
+    let minimum = Rent::default().minimum_balance(<Counter>::LEN);
+    let account = Account { lamports: minimum, data: vec![0u8; <Counter>::LEN], ..Account::default() };
+
+    let status = rent_status(&rpc, &account).unwrap();
+    assert_eq!(status, RentStatus { lamports: minimum, minimum_balance: minimum });
+    assert!(status.is_exempt());
+    assert_eq!(status.shortfall(), 0);
+
+    // 预留了空间的账户需要更高的余额
+    let padded = Account { data: vec![0u8; <Counter>::LEN + 64], ..account };
+    let status = rent_status(&rpc, &padded).unwrap();
+    assert!(!status.is_exempt());
+    assert_eq!(status.shortfall(), Rent::default().minimum_balance(<Counter>::LEN + 64) - minimum);
+}
+
+#[test]
+fn wait_for_confirmations_polls_until_the_count_is_reached() {
+    let rpc = MockRpc::new(Hash::new_unique());
+    let signature = Signature::new_unique();
+    let poll = Duration::from_millis(1);
+
+    // 节点还不知道这笔交易
+    assert_eq!(rpc.get_signature_confirmations(&signature).unwrap(), None);
+    assert!(!wait_for_confirmations(&rpc, &signature, 1, Duration::ZERO, poll).unwrap());
+
+    for count in [0, 1, 2, 3] {
+        rpc.push_confirmations(signature, Confirmations::Count(count));
+    }
+    assert!(wait_for_confirmations(&rpc, &signature, 3, Duration::from_secs(5), poll).unwrap());
+    // 确认数停在3，达不到5
+    assert!(!wait_for_confirmations(&rpc, &signature, 5, Duration::from_millis(10), poll).unwrap());
+
+    // rooted 的交易满足任何确认数
+    rpc.push_confirmations(signature, Confirmations::Rooted);
+    assert!(wait_for_confirmations(&rpc, &signature, 100, Duration::from_secs(5), poll).unwrap());
+}
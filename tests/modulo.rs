@@ -0,0 +1,59 @@
+mod common;
+
+use {
+    common::{create_counter, send, start},
+    rust_solana::{authority_instruction, pack_instruction_data, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    solana_program_test::ProgramTestContext,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+};
+
+// 模拟 GetCountMod，返回 return data 或交易错误
+async fn get_count_mod(
+    context: &mut ProgramTestContext,
+    program_id: Pubkey,
+    counter: Pubkey,
+    divisor: u32,
+) -> Result<Vec<u8>, TransactionError> {
+    let ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(counter, false)],
+        data: pack_instruction_data(&CounterInstruction::GetCountMod { divisor }),
+    };
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction =
+        Transaction::new_signed_with_payer(&[ix], Some(&context.payer.pubkey()), &[&context.payer], blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    simulation.result.unwrap()?;
+    Ok(simulation.simulation_details.unwrap().return_data.map(|data| data.data).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn get_count_mod_returns_the_remainder() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let counter = create_counter(&mut context, &program_id, &authority).await;
+
+    let set_ix = authority_instruction(
+        &program_id,
+        &counter.pubkey(),
+        &authority.pubkey(),
+        &CounterInstruction::AuthSetValue { value: 17 },
+    );
+    send(&mut context, &[set_ix], &[&authority]).await.unwrap();
+
+    for (divisor, remainder) in [(5u32, 2u32), (1, 0), (17, 0), (18, 17), (u32::MAX, 17)] {
+        let data = get_count_mod(&mut context, program_id, counter.pubkey(), divisor).await.unwrap();
+        assert_eq!(data, remainder.to_le_bytes());
+    }
+
+    let err = get_count_mod(&mut context, program_id, counter.pubkey(), 0).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use {
+    common::{create_counter, decrement_and_pay_ix, get_counter, send, start},
+    rust_solana::{authority_instruction, Count, CounterInstruction},
+    solana_program::{instruction::InstructionError, pubkey::Pubkey},
+    solana_program_test::ProgramTestContext,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+const LAMPORTS: u32 = 1_000_000_000;
+
+// 创建计数器并把计数设为 value
+async fn counter_with_value(context: &mut ProgramTestContext, program_id: &Pubkey, value: u32) -> Pubkey {
+    let authority = Keypair::new();
+    let counter = create_counter(context, program_id, &authority).await;
+    let set_ix = authority_instruction(
+        program_id,
+        &counter.pubkey(),
+        &authority.pubkey(),
+        &CounterInstruction::AuthSetValue { value },
+    );
+    send(context, &[set_ix], &[&authority]).await.unwrap();
+    counter.pubkey()
+}
+
+#[tokio::test]
+async fn decrement_and_pay_moves_lamports_with_the_count() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let counter = counter_with_value(&mut context, &program_id, 3 * LAMPORTS).await;
+    let recipient = Pubkey::new_unique();
+
+    let payer = context.payer.pubkey();
+    send(&mut context, &[decrement_and_pay_ix(&program_id, &counter, &payer, &recipient, LAMPORTS)], &[])
+        .await
+        .unwrap();
+
+    assert_eq!(get_counter(&mut context.banks_client, &counter).await.count, Count::from(2 * LAMPORTS));
+    assert_eq!(context.banks_client.get_balance(recipient).await.unwrap(), LAMPORTS as u64);
+}
+
+#[tokio::test]
+async fn decrement_and_pay_below_zero_transfers_nothing() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let counter = counter_with_value(&mut context, &program_id, LAMPORTS).await;
+    let recipient = Pubkey::new_unique();
+
+    let payer = context.payer.pubkey();
+    let pay_ix = decrement_and_pay_ix(&program_id, &counter, &payer, &recipient, 2 * LAMPORTS);
+    let err = send(&mut context, &[pay_ix], &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+
+    assert_eq!(get_counter(&mut context.banks_client, &counter).await.count, Count::from(LAMPORTS));
+    assert_eq!(context.banks_client.get_balance(recipient).await.unwrap(), 0);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use {
+    common::{create_counter, decrement_and_pay_ix, get_counter, program_test, send},
+    rust_solana::{pack_instruction_data, Count, Counter, CounterError, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program_pack::Pack,
+        pubkey::Pubkey,
+    },
+    solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+#[tokio::test]
+async fn stuck_in_progress_flag_rejects_mutations() {
+    let program_id = Pubkey::new_unique();
+    let counter = Pubkey::new_unique();
+    // 模拟修改中途残留的标志
+    let state: Counter =
+        Counter { total_ops: 1, in_progress: true, ..Counter::new(5).with_authority(Pubkey::new_unique()) };
+    let mut data = vec![0u8; <Counter>::LEN];
+    Counter::pack(state, &mut data).unwrap();
+    let mut program_test = program_test(program_id);
+    program_test.add_account(
+        counter,
+        Account { lamports: 1_000_000_000, data, owner: program_id, executable: false, rent_epoch: 0 },
+    );
+    let mut context = program_test.start_with_context().await;
+    let reentrancy = TransactionError::InstructionError(0, InstructionError::Custom(CounterError::Reentrancy as u32));
+
+    let increment_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter, false)],
+        data: pack_instruction_data(&CounterInstruction::Increment),
+    };
+    assert_eq!(send(&mut context, &[increment_ix], &[]).await.unwrap_err().unwrap(), reentrancy);
+
+    let payer = context.payer.pubkey();
+    let pay_ix = decrement_and_pay_ix(&program_id, &counter, &payer, &Pubkey::new_unique(), 1);
+    assert_eq!(send(&mut context, &[pay_ix], &[]).await.unwrap_err().unwrap(), reentrancy);
+    assert_eq!(get_counter(&mut context.banks_client, &counter).await.count, Count::from(5u32));
+}
+
+#[tokio::test]
+async fn decrement_and_pay_clears_the_flag_after_the_transfer() {
+    let program_id = Pubkey::new_unique();
+    let mut context = program_test(program_id).start_with_context().await;
+    let counter = create_counter(&mut context, &program_id, &Keypair::new()).await;
+
+    // 计数不变时同样要清除CPI期间设置的标志
+    let payer = context.payer.pubkey();
+    let pay_ix = decrement_and_pay_ix(&program_id, &counter.pubkey(), &payer, &Pubkey::new_unique(), 0);
+    send(&mut context, &[pay_ix], &[]).await.unwrap();
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert!(!state.in_progress);
+    assert_eq!(state.total_ops, 1);
+}
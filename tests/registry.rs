@@ -0,0 +1,179 @@
+mod common;
+
+use {
+    common::{create_counter_account_ix, initialize_ix, program_test, send, start},
+    rust_solana::{
+        encode_label, pack_instruction_data, Counter, CounterInstruction, Registry, ACCOUNT_VERSION,
+        REGISTRY_CAPACITY, REGISTRY_DISCRIMINATOR,
+    },
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program_pack::Pack,
+        pubkey::Pubkey,
+    },
+    solana_program_test::ProgramTestContext,
+    solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    solana_system_interface::instruction as system_instruction,
+};
+
+// 创建一个归属 owner 的空注册表账户
+async fn create_registry(context: &mut ProgramTestContext, owner: &Pubkey) -> Keypair {
+    let registry = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let create_ix = system_instruction::create_account(
+        &context.payer.pubkey(),
+        &registry.pubkey(),
+        rent.minimum_balance(Registry::LEN),
+        Registry::LEN as u64,
+        owner,
+    );
+    send(context, &[create_ix], &[&registry]).await.unwrap();
+    registry
+}
+
+// 创建并初始化一个计数器，同时登记到注册表；registry_signer 为注册表的密钥对时由注册表签名
+async fn create_registered_counter(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    registry: &Pubkey,
+    registry_signer: Option<&Keypair>,
+) -> Result<Pubkey, TransactionError> {
+    let counter = Keypair::new();
+    let authority = Keypair::new();
+    let create_ix = create_counter_account_ix(context, program_id, &counter.pubkey()).await;
+    let mut init_ix = initialize_ix(program_id, &counter.pubkey(), &authority.pubkey(), "registered");
+    init_ix.accounts.push(AccountMeta::new(*registry, registry_signer.is_some()));
+    let mut signers = vec![&counter, &authority];
+    signers.extend(registry_signer);
+    send(context, &[create_ix, init_ix], &signers).await.map_err(|err| err.unwrap())?;
+    Ok(counter.pubkey())
+}
+
+// 模拟 ListCounters，返回 return data 或交易错误
+async fn list_counters(
+    context: &mut ProgramTestContext,
+    program_id: Pubkey,
+    registry: Pubkey,
+) -> Result<Vec<u8>, TransactionError> {
+    let list_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(registry, false)],
+        data: pack_instruction_data(&CounterInstruction::ListCounters),
+    };
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction =
+        Transaction::new_signed_with_payer(&[list_ix], Some(&context.payer.pubkey()), &[&context.payer], blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    simulation.result.unwrap()?;
+    Ok(simulation.simulation_details.unwrap().return_data.map(|data| data.data).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn initialize_appends_counters_to_the_registry() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let registry = create_registry(&mut context, &program_id).await;
+
+    // 第一次登记需要注册表签名，之后不需要
+    let err = create_registered_counter(&mut context, &program_id, &registry.pubkey(), None).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(1, InstructionError::MissingRequiredSignature));
+    let first = create_registered_counter(&mut context, &program_id, &registry.pubkey(), Some(&registry))
+        .await
+        .unwrap();
+    let second = create_registered_counter(&mut context, &program_id, &registry.pubkey(), None).await.unwrap();
+
+    let account = context.banks_client.get_account(registry.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.data[0], REGISTRY_DISCRIMINATOR);
+    let stored = Registry::unpack(&account.data).unwrap();
+    assert_eq!(stored.counters().unwrap(), &[first, second]);
+
+    // ListCounters 通过 return data 返回同样的列表
+    let data = list_counters(&mut context, program_id, registry.pubkey()).await.unwrap();
+    assert_eq!(data, [first.to_bytes(), second.to_bytes()].concat());
+}
+
+#[tokio::test]
+async fn a_counter_account_is_not_accepted_as_a_registry() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+
+    // 填充后与注册表一样长的计数器
+    let victim = Keypair::new();
+    let authority = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let create_ix = system_instruction::create_account(
+        &context.payer.pubkey(),
+        &victim.pubkey(),
+        rent.minimum_balance(Registry::LEN),
+        Registry::LEN as u64,
+        &program_id,
+    );
+    let init_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(victim.pubkey(), true), AccountMeta::new_readonly(authority.pubkey(), true)],
+        data: pack_instruction_data(&CounterInstruction::InitializeWithPadding {
+            extra: (Registry::LEN - <Counter>::LEN) as u16,
+            label: encode_label("victim").unwrap(),
+        }),
+    };
+    send(&mut context, &[create_ix, init_ix], &[&victim, &authority]).await.unwrap();
+    let before = context.banks_client.get_account(victim.pubkey()).await.unwrap().unwrap();
+
+    let err = create_registered_counter(&mut context, &program_id, &victim.pubkey(), None).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(1, InstructionError::InvalidAccountData));
+    let err = list_counters(&mut context, program_id, victim.pubkey()).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+
+    let after = context.banks_client.get_account(victim.pubkey()).await.unwrap().unwrap();
+    assert_eq!(after.data, before.data);
+    assert_eq!(<Counter>::unpack_lenient(&after.data).unwrap().label, encode_label("victim").unwrap());
+}
+
+#[tokio::test]
+async fn a_registry_account_is_not_accepted_as_a_counter() {
+    let program_id = Pubkey::new_unique();
+    let registry = Pubkey::new_unique();
+
+    // 第二个字节（len）恰好等于计数器的版本字节，并且前 Counter::LEN 字节的校验和相符
+    let mut counters = [Pubkey::default(); REGISTRY_CAPACITY];
+    counters.iter_mut().for_each(|counter| *counter = Pubkey::new_unique());
+    let state = Registry { is_initialized: true, len: ACCOUNT_VERSION, counters };
+    let mut data = vec![0u8; Registry::LEN];
+    Registry::pack(state, &mut data).unwrap();
+    data[<Counter>::LEN - 1] = data[..<Counter>::LEN - 1].iter().fold(0, |acc, byte| acc ^ byte);
+    let mut program_test = program_test(program_id);
+    program_test.add_account(
+        registry,
+        Account { lamports: 1_000_000_000, data: data.clone(), owner: program_id, executable: false, rent_epoch: 0 },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let increment_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(registry, false)],
+        data: pack_instruction_data(&CounterInstruction::Increment),
+    };
+    let err = send(&mut context, &[increment_ix], &[]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+    let after = context.banks_client.get_account(registry).await.unwrap().unwrap();
+    assert_eq!(after.data, data);
+}
+
+#[tokio::test]
+async fn initialize_rejects_a_registry_owned_by_another_program() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let registry = create_registry(&mut context, &Pubkey::new_unique()).await;
+
+    let counter = Keypair::new();
+    let authority = Keypair::new();
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await;
+    let mut init_ix = initialize_ix(&program_id, &counter.pubkey(), &authority.pubkey(), "registered");
+    init_ix.accounts.push(AccountMeta::new(registry.pubkey(), false));
+    let err = send(&mut context, &[create_ix, init_ix], &[&counter, &authority]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(1, InstructionError::IncorrectProgramId));
+}
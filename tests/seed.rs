@@ -0,0 +1,82 @@
+mod common;
+
+use {
+    common::{get_counter, send, start},
+    rust_solana::{encode_label, initialize_with_seed_instruction, Counter},
+    solana_program::{instruction::InstructionError, program_pack::Pack, pubkey::Pubkey},
+    solana_program_test::ProgramTestContext,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+    solana_system_interface::instruction as system_instruction,
+};
+
+// 以 base 和种子创建归属本程序的计数器账户，返回派生地址
+async fn create_seeded_account(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    base: &Keypair,
+    seed: &str,
+) -> Pubkey {
+    let counter = Pubkey::create_with_seed(&base.pubkey(), seed, program_id).unwrap();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let create_ix = system_instruction::create_account_with_seed(
+        &context.payer.pubkey(),
+        &counter,
+        &base.pubkey(),
+        seed,
+        rent.minimum_balance(<Counter>::LEN),
+        <Counter>::LEN as u64,
+        program_id,
+    );
+    send(context, &[create_ix], &[base]).await.unwrap();
+    counter
+}
+
+#[tokio::test]
+async fn initialize_with_seed_accepts_the_derived_address() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let base = Keypair::new();
+    let counter = create_seeded_account(&mut context, &program_id, &base, "counter-1").await;
+
+    let authority = Keypair::new();
+    let init_ix = initialize_with_seed_instruction(
+        &program_id,
+        &counter,
+        &authority.pubkey(),
+        &base.pubkey(),
+        "counter-1",
+        encode_label("seeded").unwrap(),
+    );
+    send(&mut context, &[init_ix], &[&authority, &base]).await.unwrap();
+
+    let counter = get_counter(&mut context.banks_client, &counter).await;
+    assert!(counter.is_initialized);
+    assert_eq!(counter.authority, authority.pubkey());
+    assert_eq!(counter.label, encode_label("seeded").unwrap());
+}
+
+#[tokio::test]
+async fn initialize_with_seed_rejects_a_mismatched_seed() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let base = Keypair::new();
+    let counter = create_seeded_account(&mut context, &program_id, &base, "counter-1").await;
+
+    let authority = Keypair::new();
+    let init_ix = initialize_with_seed_instruction(
+        &program_id,
+        &counter,
+        &authority.pubkey(),
+        &base.pubkey(),
+        "counter-2",
+        encode_label("seeded").unwrap(),
+    );
+    let err = send(&mut context, &[init_ix], &[&authority, &base]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidSeeds));
+
+    let account = context.banks_client.get_account(counter).await.unwrap().unwrap();
+    assert!(!<Counter>::unpack_unchecked(&account.data).unwrap().is_initialized);
+}
@@ -0,0 +1,182 @@
+use {
+    rust_solana::{
+        count_to_i64, Count, Counter, CounterError, LABEL_LEN, LEGACY_COUNTER_LEN,
+    },
+    solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey},
+};
+
+#[test]
+fn count_to_i64_accepts_values_up_to_i64_max() {
+    assert_eq!(count_to_i64(0u32), Ok(0));
+    assert_eq!(count_to_i64(u32::MAX), Ok(u32::MAX as i64));
+    assert_eq!(count_to_i64(i64::MAX as u64), Ok(i64::MAX));
+    assert_eq!(count_to_i64(i64::MAX as u128), Ok(i64::MAX));
+}
+
+#[test]
+fn count_to_i64_rejects_values_above_i64_max() {
+    assert_eq!(count_to_i64(i64::MAX as u64 + 1), Err(CounterError::ValueTooLarge));
+    assert_eq!(count_to_i64(u64::MAX), Err(CounterError::ValueTooLarge));
+    assert_eq!(count_to_i64(i64::MAX as u128 + 1), Err(CounterError::ValueTooLarge));
+}
+
+#[test]
+fn unpack_detects_a_flipped_bit() {
+    let counter: Counter = Counter {
+        is_initialized: true,
+        count: Count::from(7u32),
+        authority: Pubkey::new_unique(),
+        label: [0u8; LABEL_LEN],
+        expires_at: 0,
+        total_ops: 0,
+        mint: Pubkey::default(),
+        decay_per_slot: 0,
+        last_slot: 0,
+        lifetime_limit: 0,
+        min_seconds_between: 0,
+        last_ts: 0,
+        in_progress: false,
+    };
+    let mut buffer = vec![0u8; <Counter>::LEN];
+    Counter::pack(counter, &mut buffer).unwrap();
+    assert!(<Counter>::unpack(&buffer).is_ok());
+
+    // 翻转计数值所在字节的最低位
+    buffer[2] ^= 1;
+    assert_eq!(
+        <Counter>::unpack(&buffer).unwrap_err(),
+        ProgramError::from(CounterError::CorruptData)
+    );
+}
+
+#[test]
+fn lenient_unpack_ignores_trailing_data() {
+    let counter: Counter = Counter {
+        is_initialized: true,
+        count: Count::from(3u32),
+        authority: Pubkey::new_unique(),
+        label: [0u8; LABEL_LEN],
+        expires_at: 0,
+        total_ops: 0,
+        mint: Pubkey::default(),
+        decay_per_slot: 0,
+        last_slot: 0,
+        lifetime_limit: 0,
+        min_seconds_between: 0,
+        last_ts: 0,
+        in_progress: false,
+    };
+    let mut buffer = vec![0u8; <Counter>::LEN + 16];
+    Counter::pack(counter, &mut buffer[..<Counter>::LEN]).unwrap();
+    buffer[<Counter>::LEN..].fill(0xff);
+
+    let unpacked = <Counter>::unpack_lenient(&buffer).unwrap();
+    assert_eq!(unpacked.count, Count::from(3u32));
+    // 严格版本仍然拒绝多余数据
+    assert_eq!(<Counter>::unpack(&buffer).unwrap_err(), ProgramError::InvalidAccountData);
+}
+
+#[test]
+fn lenient_unpack_reads_legacy_five_byte_accounts() {
+    let legacy = [1u8, 0x2a, 0x01, 0x00, 0x00];
+    assert_eq!(legacy.len(), LEGACY_COUNTER_LEN);
+
+    let counter = <Counter>::unpack_lenient(&legacy).unwrap();
+    assert!(counter.is_initialized);
+    assert_eq!(counter.count, Count::from(0x012au32));
+    assert_eq!(counter.authority, Pubkey::default());
+}
+
+fn packed_counter() -> Vec<u8> {
+    let counter: Counter = Counter::new(9).with_authority(Pubkey::new_unique());
+    let mut buffer = vec![0u8; <Counter>::LEN];
+    Counter::pack(counter, &mut buffer).unwrap();
+    buffer
+}
+
+#[test]
+fn unpack_from_slice_rejects_short_input_and_tolerates_trailing_data() {
+    let buffer = packed_counter();
+
+    assert_eq!(<Counter>::unpack_from_slice(&buffer[..4]).unwrap_err(), ProgramError::InvalidAccountData);
+    assert_eq!(
+        <Counter>::unpack_from_slice(&buffer[..<Counter>::LEN - 1]).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+
+    let mut longer = buffer.clone();
+    longer.push(0);
+    assert_eq!(<Counter>::unpack_from_slice(&longer).unwrap().count, Count::from(9u32));
+}
+
+#[test]
+fn checked_unpack_requires_exact_length() {
+    let buffer = packed_counter();
+    assert_eq!(<Counter>::unpack(&buffer).unwrap().count, Count::from(9u32));
+
+    assert_eq!(<Counter>::unpack(&buffer[..4]).unwrap_err(), ProgramError::InvalidAccountData);
+    assert_eq!(
+        <Counter>::unpack(&buffer[..<Counter>::LEN - 1]).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+    let mut longer = buffer.clone();
+    longer.push(0);
+    assert_eq!(<Counter>::unpack(&longer).unwrap_err(), ProgramError::InvalidAccountData);
+}
+
+#[test]
+fn counter_builder_sets_only_the_requested_fields() {
+    let authority = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let counter: Counter = Counter::new(4)
+        .with_authority(authority)
+        .with_label([7u8; LABEL_LEN])
+        .with_expiry(1_700_000_000)
+        .with_mint(mint)
+        .with_decay(2, 50)
+        .with_lifetime_limit(10)
+        .with_cooldown(30);
+    assert_eq!(
+        counter,
+        Counter {
+            is_initialized: true,
+            count: Count::from(4u32),
+            authority,
+            label: [7u8; LABEL_LEN],
+            expires_at: 1_700_000_000,
+            total_ops: 0,
+            mint,
+            decay_per_slot: 2,
+            last_slot: 50,
+            lifetime_limit: 10,
+            min_seconds_between: 30,
+            last_ts: 0,
+            in_progress: false,
+        }
+    );
+
+    // new 只比默认值多了初始化标志和计数
+    assert_eq!(Counter::new(Count::from(0u32)), Counter { is_initialized: true, ..Counter::default() });
+}
+
+// base64 解码函数属于 client feature，--no-default-features 构建链上程序时不存在
+#[cfg(feature = "client")]
+mod base64 {
+    use {
+        rust_solana::{decode_account_base64, decode_counter_base64},
+        solana_program::program_error::ProgramError,
+    };
+
+    #[test]
+    fn decode_counter_base64_separates_encoding_and_layout_errors() {
+        assert_eq!(decode_counter_base64("not base64!").unwrap_err(), ProgramError::InvalidArgument);
+        // 合法的base64，但长度不是计数器账户
+        assert_eq!(decode_counter_base64("AAAA").unwrap_err(), ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn decode_account_base64_returns_the_raw_bytes() {
+        assert_eq!(decode_account_base64("AQID").unwrap(), vec![1, 2, 3]);
+        assert_eq!(decode_account_base64("not base64!").unwrap_err(), ProgramError::InvalidArgument);
+    }
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use {
+    common::{counter_ix, create_counter, create_counter_account_ix, get_counter, send, start},
+    rust_solana::{pack_instruction_data, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+fn swap_ix(program_id: &Pubkey, first: &Pubkey, second: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*first, false), AccountMeta::new(*second, false)],
+        data: pack_instruction_data(&CounterInstruction::Swap),
+    }
+}
+
+#[tokio::test]
+async fn swap_exchanges_both_counts() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let first = create_counter(&mut context, &program_id, &authority).await;
+    let second = create_counter(&mut context, &program_id, &authority).await;
+
+    let first_ix = counter_ix(&program_id, &first.pubkey(), &CounterInstruction::IncrementUntil { target: 3 });
+    let second_ix = counter_ix(&program_id, &second.pubkey(), &CounterInstruction::IncrementUntil { target: 7 });
+    send(&mut context, &[first_ix, second_ix], &[]).await.unwrap();
+
+    send(&mut context, &[swap_ix(&program_id, &first.pubkey(), &second.pubkey())], &[]).await.unwrap();
+
+    let first_counter = get_counter(&mut context.banks_client, &first.pubkey()).await;
+    let second_counter = get_counter(&mut context.banks_client, &second.pubkey()).await;
+    assert_eq!(first_counter.count, 7);
+    assert_eq!(second_counter.count, 3);
+}
+
+#[tokio::test]
+async fn swap_with_an_uninitialized_counter_leaves_both_unchanged() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let first = create_counter(&mut context, &program_id, &authority).await;
+    let first_ix = counter_ix(&program_id, &first.pubkey(), &CounterInstruction::Increment);
+    send(&mut context, &[first_ix], &[]).await.unwrap();
+
+    let second = Keypair::new();
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &second.pubkey()).await;
+    send(&mut context, &[create_ix], &[&second]).await.unwrap();
+
+    let before = get_counter(&mut context.banks_client, &first.pubkey()).await;
+    let err = send(&mut context, &[swap_ix(&program_id, &first.pubkey(), &second.pubkey())], &[])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::UninitializedAccount));
+
+    let after = get_counter(&mut context.banks_client, &first.pubkey()).await;
+    assert_eq!(after, before);
+    let second_account = context.banks_client.get_account(second.pubkey()).await.unwrap().unwrap();
+    assert!(second_account.data.iter().all(|&b| b == 0));
+}
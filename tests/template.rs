@@ -0,0 +1,88 @@
+mod common;
+
+use {
+    common::{create_counter_account_ix, get_counter, send, start},
+    rust_solana::{authority_instruction, encode_label, pack_instruction_data, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+fn from_template_ix(program_id: Pubkey, counter: Pubkey, template: Pubkey) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(counter, true), AccountMeta::new_readonly(template, false)],
+        data: pack_instruction_data(&CounterInstruction::InitializeFromTemplate),
+    }
+}
+
+#[tokio::test]
+async fn initialize_from_template_copies_configuration_but_not_the_count() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+    let authority = Keypair::new();
+    let template = Keypair::new();
+
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &template.pubkey()).await;
+    let init_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(template.pubkey(), true), AccountMeta::new_readonly(authority.pubkey(), true)],
+        data: pack_instruction_data(&CounterInstruction::InitializeWithCooldown {
+            min_seconds_between: 30,
+            label: encode_label("pool").unwrap(),
+        }),
+    };
+    let set_ix = authority_instruction(
+        &program_id,
+        &template.pubkey(),
+        &authority.pubkey(),
+        &CounterInstruction::AuthSetValue { value: 42 },
+    );
+    send(&mut context, &[create_ix, init_ix, set_ix], &[&template, &authority]).await.unwrap();
+
+    // 授权人不必签名
+    let counter = Keypair::new();
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await;
+    let init_ix = from_template_ix(program_id, counter.pubkey(), template.pubkey());
+    send(&mut context, &[create_ix, init_ix], &[&counter]).await.unwrap();
+
+    let template_state = get_counter(&mut context.banks_client, &template.pubkey()).await;
+    let state = get_counter(&mut context.banks_client, &counter.pubkey()).await;
+    assert_eq!(state.count, 0);
+    assert_eq!(state.total_ops, 1);
+    assert_eq!(state.authority, authority.pubkey());
+    assert_eq!(state.label, template_state.label);
+    assert_eq!(state.min_seconds_between, 30);
+    assert_eq!(template_state.count, 42);
+}
+
+#[tokio::test]
+async fn initialize_from_template_rejects_invalid_templates() {
+    let program_id = Pubkey::new_unique();
+    let mut context = start(program_id).await;
+
+    // 未初始化的模板
+    let template = Keypair::new();
+    let counter = Keypair::new();
+    let create_template_ix = create_counter_account_ix(&mut context, &program_id, &template.pubkey()).await;
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await;
+    let init_ix = from_template_ix(program_id, counter.pubkey(), template.pubkey());
+    let err = send(&mut context, &[create_template_ix, create_ix, init_ix], &[&template, &counter])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(err, TransactionError::InstructionError(2, InstructionError::UninitializedAccount));
+
+    // 不属于当前程序的模板
+    let payer = context.payer.pubkey();
+    let counter = Keypair::new();
+    let create_ix = create_counter_account_ix(&mut context, &program_id, &counter.pubkey()).await;
+    let init_ix = from_template_ix(program_id, counter.pubkey(), payer);
+    let err = send(&mut context, &[create_ix, init_ix], &[&counter]).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(1, InstructionError::IncorrectProgramId));
+}
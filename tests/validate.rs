@@ -0,0 +1,77 @@
+mod common;
+
+use {
+    common::program_test,
+    rust_solana::{pack_instruction_data, AccountStatus, Counter, CounterInstruction},
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program_pack::Pack,
+        pubkey::Pubkey,
+    },
+    solana_sdk::{account::Account, signature::Signer, transaction::Transaction},
+};
+
+fn valid_counter_data() -> Vec<u8> {
+    let counter: Counter = Counter::new(7).with_authority(Pubkey::new_unique());
+    let mut data = vec![0u8; <Counter>::LEN];
+    Counter::pack(counter, &mut data).unwrap();
+    data
+}
+
+// 把给定数据放进一个属于本程序的账户，模拟 Validate 并返回状态字节
+async fn validate(data: Vec<u8>) -> u8 {
+    let program_id = Pubkey::new_unique();
+    let counter = Pubkey::new_unique();
+    let mut program_test = program_test(program_id);
+    program_test.add_account(
+        counter,
+        Account { lamports: 1_000_000_000, data, owner: program_id, executable: false, rent_epoch: 0 },
+    );
+    let context = program_test.start_with_context().await;
+
+    let validate_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(counter, false)],
+        data: pack_instruction_data(&CounterInstruction::Validate),
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[validate_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    let details = simulation.simulation_details.unwrap();
+    assert!(simulation.result.unwrap().is_ok());
+    details.return_data.unwrap().data[0]
+}
+
+#[tokio::test]
+async fn validate_reports_a_current_format_account_as_valid() {
+    assert_eq!(validate(valid_counter_data()).await, AccountStatus::Valid as u8);
+}
+
+#[tokio::test]
+async fn validate_reports_legacy_and_other_versions_as_wrong_version() {
+    assert_eq!(validate(vec![1, 7, 0, 0, 0]).await, AccountStatus::WrongVersion as u8);
+
+    let mut data = valid_counter_data();
+    data[1] = data[1].wrapping_add(1);
+    assert_eq!(validate(data).await, AccountStatus::WrongVersion as u8);
+}
+
+#[tokio::test]
+async fn validate_reports_bad_checksums_and_short_data_as_corrupt() {
+    let mut data = valid_counter_data();
+    data[2] ^= 0x01;
+    assert_eq!(validate(data).await, AccountStatus::Corrupt as u8);
+
+    let mut data = valid_counter_data();
+    data.truncate(<Counter>::LEN - 1);
+    assert_eq!(validate(data).await, AccountStatus::Corrupt as u8);
+}
+
+#[tokio::test]
+async fn validate_reports_zeroed_accounts_as_uninitialized() {
+    assert_eq!(validate(vec![0u8; <Counter>::LEN]).await, AccountStatus::Uninitialized as u8);
+}
@@ -0,0 +1,26 @@
+use std::process::Command;
+
+// 需要本地运行 solana-test-validator（http://localhost:8899），并且程序已部署到客户端中的 PROGRAM_ID：
+//   solana-test-validator
+//   solana program deploy target/deploy/rust_solana.so --program-id <程序密钥对>
+//   cargo test --test validator -- --ignored
+#[test]
+#[ignore = "requires a running solana-test-validator with the program deployed"]
+fn demo_flow_against_local_validator() {
+    let output = Command::new(env!("CARGO_BIN_EXE_counter-client"))
+        .args(["--json", "demo"])
+        .output()
+        .expect("failed to run counter-client");
+
+    assert!(
+        output.status.success(),
+        "counter-client failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let result: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be a single JSON object");
+    // 演示流程：增加两次、减少一次
+    assert_eq!(result["count"], 1);
+    assert!(result["signature"].is_string());
+}